@@ -0,0 +1,355 @@
+//! Driver options gathered for construction.
+
+use embedded_hal::digital::OutputPin;
+
+use crate::{
+    AdaptiveRefresh, AddressPins, BitOrder, Blocking, BufferStorage, ChainOrder, Compensation,
+    DeadPanelMode, EnableLine, EnablePolarity, Error, P10Led, PanelGeometry, PixelRemap, Profile,
+    Rotation, RowSelector, ScanMode, DEFAULT_GROUP_ORDER,
+};
+
+/// Options applied by [`P10Led::new_with_config`], each matching a setter of the driver.
+///
+/// Start from [`Config::new`] (or `Default`) and change what differs with the `with_`
+/// methods; the struct is non-exhaustive, so options added in later releases do not break
+/// existing code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+// Configs compare the remap by address, which is good enough to spot a change.
+#[allow(unpredictable_function_pointer_comparisons)]
+#[non_exhaustive]
+pub struct Config {
+    /// Panels across and down, `None` for the `PX` x `PY` of the driver type.
+    pub layout: Option<(usize, usize)>,
+    pub bit_order: BitOrder,
+    pub byte_reversal: bool,
+    pub enable_polarity: EnablePolarity,
+    pub row_map: Option<[u8; 16]>,
+    pub group_order: [u8; 4],
+    /// Panel levels, also used for uniform dimming.
+    pub compensation: Compensation,
+    pub dead_panels: u32,
+    pub dead_panel_mode: DeadPanelMode,
+    pub flipped_panels: u32,
+    pub chain_order: ChainOrder,
+    pub scan_mode: ScanMode,
+    pub scan_order: Option<[u8; 16]>,
+    pub profile: Profile,
+    pub rotation: Rotation,
+    /// Horizontal and vertical mirroring.
+    pub mirror: (bool, bool),
+    /// Full refreshes per second for the paced refresh, `None` to not pace it.
+    pub refresh_rate: Option<u16>,
+    /// Brightness of the whole display, `255` for full brightness.
+    pub brightness: u8,
+    /// Map the brightness through a gamma curve.
+    pub gamma: bool,
+    pub inverted: bool,
+    pub shift_blanking: bool,
+    pub double_buffered: bool,
+    pub precompute: bool,
+    /// Minimum interval between limited flushes in microseconds, `None` to keep the one of
+    /// the profile.
+    pub min_flush_interval_us: Option<u32>,
+    /// Idle gaps for static content, `None` to keep the ones of the profile.
+    pub adaptive_refresh: Option<AdaptiveRefresh>,
+    pub pixel_remap: Option<PixelRemap>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Config {
+    /// The defaults of [`P10Led::new`].
+    pub const fn new() -> Self {
+        Self {
+            layout: None,
+            bit_order: BitOrder::MsbFirst,
+            byte_reversal: false,
+            enable_polarity: EnablePolarity::ActiveHigh,
+            row_map: None,
+            group_order: DEFAULT_GROUP_ORDER,
+            compensation: Compensation::None,
+            dead_panels: 0,
+            dead_panel_mode: DeadPanelMode::Discard,
+            flipped_panels: 0,
+            chain_order: ChainOrder::RowMajor,
+            scan_mode: ScanMode::Quarter,
+            scan_order: None,
+            profile: Profile::MaxRefresh,
+            rotation: Rotation::Deg0,
+            mirror: (false, false),
+            refresh_rate: None,
+            brightness: u8::MAX,
+            gamma: false,
+            inverted: false,
+            shift_blanking: false,
+            double_buffered: false,
+            precompute: false,
+            min_flush_interval_us: None,
+            adaptive_refresh: None,
+            pixel_remap: None,
+        }
+    }
+
+    pub const fn with_layout(mut self, panels_x: usize, panels_y: usize) -> Self {
+        self.layout = Some((panels_x, panels_y));
+        self
+    }
+
+    pub const fn with_bit_order(mut self, order: BitOrder) -> Self {
+        self.bit_order = order;
+        self
+    }
+
+    pub const fn with_byte_reversal(mut self, on: bool) -> Self {
+        self.byte_reversal = on;
+        self
+    }
+
+    pub const fn with_enable_polarity(mut self, polarity: EnablePolarity) -> Self {
+        self.enable_polarity = polarity;
+        self
+    }
+
+    pub const fn with_row_map(mut self, map: [u8; 16]) -> Self {
+        self.row_map = Some(map);
+        self
+    }
+
+    pub const fn with_group_order(mut self, order: [u8; 4]) -> Self {
+        self.group_order = order;
+        self
+    }
+
+    pub const fn with_compensation(mut self, compensation: Compensation) -> Self {
+        self.compensation = compensation;
+        self
+    }
+
+    pub const fn with_dead_panels(mut self, mask: u32, mode: DeadPanelMode) -> Self {
+        self.dead_panels = mask;
+        self.dead_panel_mode = mode;
+        self
+    }
+
+    pub const fn with_flipped_panels(mut self, mask: u32) -> Self {
+        self.flipped_panels = mask;
+        self
+    }
+
+    pub const fn with_chain_order(mut self, order: ChainOrder) -> Self {
+        self.chain_order = order;
+        self
+    }
+
+    pub const fn with_scan_mode(mut self, mode: ScanMode) -> Self {
+        self.scan_mode = mode;
+        self
+    }
+
+    pub const fn with_scan_order(mut self, order: [u8; 16]) -> Self {
+        self.scan_order = Some(order);
+        self
+    }
+
+    pub const fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub const fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub const fn with_mirror(mut self, x: bool, y: bool) -> Self {
+        self.mirror = (x, y);
+        self
+    }
+
+    pub const fn with_refresh_rate(mut self, hz: u16) -> Self {
+        self.refresh_rate = Some(hz);
+        self
+    }
+
+    pub const fn with_brightness(mut self, level: u8) -> Self {
+        self.brightness = level;
+        self
+    }
+
+    pub const fn with_gamma(mut self, on: bool) -> Self {
+        self.gamma = on;
+        self
+    }
+
+    pub const fn with_inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    pub const fn with_shift_blanking(mut self, on: bool) -> Self {
+        self.shift_blanking = on;
+        self
+    }
+
+    pub const fn with_double_buffered(mut self, enabled: bool) -> Self {
+        self.double_buffered = enabled;
+        self
+    }
+
+    pub const fn with_precompute(mut self, enabled: bool) -> Self {
+        self.precompute = enabled;
+        self
+    }
+
+    pub const fn with_min_flush_interval_us(mut self, interval: u32) -> Self {
+        self.min_flush_interval_us = Some(interval);
+        self
+    }
+
+    pub const fn with_adaptive_refresh(mut self, adaptive: AdaptiveRefresh) -> Self {
+        self.adaptive_refresh = Some(adaptive);
+        self
+    }
+
+    pub const fn with_pixel_remap(mut self, remap: PixelRemap) -> Self {
+        self.pixel_remap = Some(remap);
+        self
+    }
+}
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Apply all options of `config` except the layout, validated like the setters.
+    pub fn configure(&mut self, config: &Config) -> Result<(), Error> {
+        self.set_scan_mode(config.scan_mode)?;
+        self.set_scan_order(config.scan_order)?;
+        self.set_row_map(config.row_map)?;
+        self.set_row_group_order(config.group_order)?;
+        self.set_dead_panels(config.dead_panels, config.dead_panel_mode)?;
+        self.set_flipped_panels(config.flipped_panels)?;
+        self.set_chain_order(config.chain_order);
+        self.set_bit_order(config.bit_order);
+        self.set_byte_reversal(config.byte_reversal);
+        self.set_enable_polarity(config.enable_polarity)?;
+        self.set_inverted(config.inverted);
+        self.set_shift_blanking(config.shift_blanking);
+        self.set_compensation(config.compensation)?;
+        self.set_gamma(config.gamma)?;
+        self.set_brightness(config.brightness)?;
+        self.set_profile(config.profile);
+        self.set_refresh_rate(config.refresh_rate);
+        if let Some(interval) = config.min_flush_interval_us {
+            self.set_min_flush_interval_us(interval);
+        }
+        if let Some(adaptive) = config.adaptive_refresh {
+            self.set_adaptive_refresh(Some(adaptive));
+        }
+        self.set_rotation(config.rotation);
+        self.set_mirror(config.mirror.0, config.mirror.1);
+        self.set_pixel_remap(config.pixel_remap)?;
+        self.set_double_buffered(config.double_buffered);
+        self.set_precompute(config.precompute);
+        Ok(())
+    }
+}
+
+impl<
+        SPI: embedded_hal::spi::SpiBus,
+        E: EnableLine,
+        A: OutputPin,
+        B: OutputPin,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > P10Led<SPI, E, AddressPins<A, B>, L, PX, PY, Blocking, G>
+{
+    /// Create a driver with the options of `config`.
+    pub fn new_with_config(
+        spi: SPI,
+        enable: E,
+        pin_a: A,
+        pin_b: B,
+        latch: L,
+        config: &Config,
+    ) -> Result<Self, Error> {
+        let (panels_x, panels_y) = config.layout.unwrap_or((PX, PY));
+        let mut driver =
+            Self::new_with_layout(spi, enable, pin_a, pin_b, latch, panels_x, panels_y)?;
+        driver.configure(config)?;
+        Ok(driver)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<
+        SPI: embedded_hal_async::spi::SpiDevice,
+        E: EnableLine,
+        A: OutputPin,
+        B: OutputPin,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > P10Led<SPI, E, AddressPins<A, B>, L, PX, PY, crate::Async, G>
+{
+    /// Create an async driver with the options of `config`.
+    pub fn new_async_with_config(
+        spi: SPI,
+        enable: E,
+        pin_a: A,
+        pin_b: B,
+        latch: L,
+        config: &Config,
+    ) -> Result<Self, Error> {
+        let (panels_x, panels_y) = config.layout.unwrap_or((PX, PY));
+        let mut driver =
+            Self::new_async_with_layout(spi, enable, pin_a, pin_b, latch, panels_x, panels_y)?;
+        driver.configure(config)?;
+        Ok(driver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{driver, Driver};
+
+    #[test]
+    fn profile_sets_what_the_config_leaves_open() {
+        let (mut display, _spi): (Driver, _) = driver();
+        let config = Config::new().with_profile(Profile::MinCpu);
+        display.configure(&config).unwrap();
+        assert_eq!(display.profile(), Profile::MinCpu);
+        assert_eq!(display.min_flush_interval_us(), 20_000);
+        assert_eq!(
+            display.adaptive_refresh(),
+            Profile::MinCpu.adaptive_refresh()
+        );
+
+        let adaptive = AdaptiveRefresh {
+            idle_after_us: 100,
+            idle_gap_us: 10,
+        };
+        let config = config
+            .with_min_flush_interval_us(5_000)
+            .with_adaptive_refresh(adaptive);
+        display.configure(&config).unwrap();
+        assert_eq!(display.min_flush_interval_us(), 5_000);
+        assert_eq!(display.adaptive_refresh(), Some(adaptive));
+    }
+}
@@ -0,0 +1,379 @@
+//! Commissioning and diagnostic routines.
+
+use embedded_hal::{digital::OutputPin, spi::SpiBus};
+
+use crate::refresh::{ready, Pause, Transfer};
+
+use crate::{
+    font::TINY_3X5, Blocking, BufferStorage, EnableLine, Error, P10Led, PanelGeometry, RowSelector,
+};
+
+/// Duration of one refresh while a diagnostic pattern is held, in microseconds.
+const REFRESH_US: u32 = 1000;
+
+/// Parameters of [`P10Led::burn_test`].
+#[derive(Clone, Copy, Debug)]
+pub struct BurnTest {
+    /// Share of each row period the LEDs are enabled, capped at 100.
+    pub duty_percent: u8,
+    /// Total test duration in milliseconds.
+    pub duration_ms: u32,
+    /// Current of a single lit LED at full duty in milliamps, from the module datasheet.
+    pub led_current_ma: u16,
+}
+
+impl BurnTest {
+    fn on_us(&self, row_hold_us: u32) -> u32 {
+        row_hold_us * self.duty_percent.min(100) as u32 / 100
+    }
+
+    /// Estimated average supply current in milliamps with `leds` pixels lit, `scan_rows`
+    /// sharing the on time.
+    fn estimated_ma(&self, leds: usize, scan_rows: u8) -> u32 {
+        (leds / scan_rows as usize) as u32
+            * self.led_current_ma as u32
+            * self.duty_percent.min(100) as u32
+            / 100
+    }
+}
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Time each row stays enabled while a diagnostic pattern is held.
+    fn row_hold_us(&self) -> u32 {
+        REFRESH_US / self.scan_rows() as u32
+    }
+
+    /// Column and row in the layout of the panel at chain index `chain`, see
+    /// [`ChainOrder`](crate::ChainOrder).
+    fn layout_slot(&self, chain: usize) -> (usize, usize) {
+        (0..self.panels_y)
+            .flat_map(|row| (0..self.panels_x).map(move |col| (col, row)))
+            .find(|&(col, row)| self.chain_index(col, row) == chain)
+            .unwrap_or((0, 0))
+    }
+
+    /// Light the panel at chain index `chain`.
+    fn fill_panel(&mut self, chain: usize) {
+        let (col, row) = self.layout_slot(chain);
+        let x0 = col * Self::PANEL_WIDTH;
+        let y0 = row * Self::PANEL_HEIGHT;
+        for y in y0..y0 + Self::PANEL_HEIGHT {
+            for x in x0..x0 + Self::PANEL_WIDTH {
+                self.put_pixel(x, y, true);
+            }
+        }
+    }
+
+    /// Replace the framebuffer drawn into, the back buffer while double buffering, with a
+    /// layout diagnostic overlay.
+    ///
+    /// Every panel gets a border and its index along the data chain, see
+    /// [`ChainOrder`](crate::ChainOrder), in the top-left corner. The right edge carries a
+    /// marker per row whose length is the row's scan group plus one, so swapped address
+    /// lines or a wrong row interleave show up as a broken 1-2-3-4 staircase.
+    ///
+    /// The overlay is drawn in layout coordinates, rotation and mirroring do not apply to it.
+    pub fn draw_panel_overlay(&mut self) {
+        self.fill_all(false);
+        for panel in 0..self.panels() {
+            let (col, row) = self.layout_slot(panel);
+            let x0 = col * Self::PANEL_WIDTH;
+            let y0 = row * Self::PANEL_HEIGHT;
+            let x1 = x0 + Self::PANEL_WIDTH - 1;
+            let y1 = y0 + Self::PANEL_HEIGHT - 1;
+            for x in x0..=x1 {
+                self.put_pixel(x, y0, true);
+                self.put_pixel(x, y1, true);
+            }
+            for y in y0..=y1 {
+                self.put_pixel(x0, y, true);
+                self.put_pixel(x1, y, true);
+            }
+
+            let digits = [b'0' + (panel / 10 % 10) as u8, b'0' + (panel % 10) as u8];
+            let mut x = x0 + 2;
+            for &digit in &digits[(panel < 10) as usize..] {
+                let glyph = TINY_3X5.glyph(digit as char).unwrap_or(&[]);
+                for (column, &bits) in glyph.iter().enumerate() {
+                    for row in 0..TINY_3X5.height as usize {
+                        if bits & (1 << row) != 0 {
+                            self.put_pixel(x + column, y0 + 2 + row, true);
+                        }
+                    }
+                }
+                x += TINY_3X5.advance() as usize;
+            }
+
+            for row in 1..Self::PANEL_HEIGHT - 1 {
+                for dx in 0..=row % 4 {
+                    self.put_pixel(x1 - 2 - dx, y0 + row, true);
+                }
+            }
+        }
+    }
+}
+
+impl<
+        SPI: Transfer<MODE>,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Keep refreshing the current framebuffer for about `ms` milliseconds, then blank the output.
+    async fn hold(&mut self, delay: &mut impl Pause<MODE>, ms: u32) -> Result<(), Error> {
+        for _ in 0..ms {
+            for _ in 0..self.scan_rows() {
+                self.fill_cache();
+                self.send_cache().await?;
+
+                self.next_row()?;
+                delay.pause_us(self.row_hold_us()).await;
+            }
+        }
+        self.blank_output().await
+    }
+
+    /// Run a diagnostic on the displayed framebuffer and clear it afterwards, also on
+    /// failure. Double buffering is suspended meanwhile, so the patterns are drawn where they
+    /// are shown and drawing pending in the back buffer is kept.
+    async fn diagnose<T>(
+        &mut self,
+        run: impl AsyncFnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let double_buffered = core::mem::replace(&mut self.double_buffered, false);
+        let result = run(self).await;
+        self.fill_all(false);
+        self.double_buffered = double_buffered;
+        result
+    }
+
+    async fn run_self_test(
+        &mut self,
+        delay: &mut impl Pause<MODE>,
+        step_ms: u32,
+    ) -> Result<(), Error> {
+        self.diagnose(async |display| display.self_test_steps(delay, step_ms).await)
+            .await
+    }
+
+    async fn self_test_steps(
+        &mut self,
+        delay: &mut impl Pause<MODE>,
+        step_ms: u32,
+    ) -> Result<(), Error> {
+        for y in 0..self.height() {
+            self.fill_bitmap(false);
+            for x in 0..self.width() {
+                self.put_pixel(x, y, true);
+            }
+            self.hold(delay, step_ms).await?;
+        }
+        for x in 0..self.width() {
+            self.fill_bitmap(false);
+            for y in 0..self.height() {
+                self.put_pixel(x, y, true);
+            }
+            self.hold(delay, step_ms).await?;
+        }
+        for panel in 0..self.panels() {
+            self.fill_bitmap(false);
+            self.fill_panel(panel);
+            self.hold(delay, step_ms).await?;
+        }
+        self.fill_bitmap(true);
+        self.hold(delay, step_ms).await?;
+        self.fill_bitmap(false);
+        self.hold(delay, step_ms).await
+    }
+
+    async fn run_burn_test(
+        &mut self,
+        delay: &mut impl Pause<MODE>,
+        test: BurnTest,
+        report: impl FnMut(u32),
+    ) -> Result<u32, Error> {
+        self.diagnose(async |display| display.burn_test_steps(delay, test, report).await)
+            .await
+    }
+
+    async fn burn_test_steps(
+        &mut self,
+        delay: &mut impl Pause<MODE>,
+        test: BurnTest,
+        mut report: impl FnMut(u32),
+    ) -> Result<u32, Error> {
+        let estimate = test.estimated_ma(self.width() * self.height(), self.scan_rows());
+        let row_hold_us = self.row_hold_us();
+        let on_us = test.on_us(row_hold_us);
+        self.fill_bitmap(true);
+
+        for ms in 0..test.duration_ms {
+            if ms % 1000 == 0 {
+                report(estimate);
+            }
+            for _ in 0..self.scan_rows() {
+                self.fill_cache();
+                self.send_cache().await?;
+
+                self.next_row()?;
+                delay.pause_us(on_us).await;
+                self.set_output(false)?;
+                delay.pause_us(row_hold_us - on_us).await;
+            }
+        }
+        self.blank_output().await?;
+        Ok(estimate)
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>
+{
+    /// Run a commissioning test sequence, holding each step for `step_ms` milliseconds.
+    ///
+    /// Walks single rows top to bottom, single columns left to right, fills each panel
+    /// in chain order and finishes with all-on and all-off. This makes dead outputs, swapped
+    /// address lines and a wrong chain order easy to spot. The patterns are shown on the
+    /// displayed framebuffer, also while double buffering, and it is left blank afterwards,
+    /// so redraw the content; drawing pending in the back buffer is kept.
+    pub fn self_test(
+        &mut self,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+        step_ms: u32,
+    ) -> Result<(), Error> {
+        ready(self.run_self_test(delay, step_ms))
+    }
+
+    /// Drive all pixels on at a capped duty for verifying PSU sizing.
+    ///
+    /// `report` is called about once a second with the estimated supply current in milliamps,
+    /// which is also returned. The displayed framebuffer is left blank afterwards, like with
+    /// [`self_test`](Self::self_test).
+    pub fn burn_test(
+        &mut self,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+        test: BurnTest,
+        report: impl FnMut(u32),
+    ) -> Result<u32, Error> {
+        ready(self.run_burn_test(delay, test, report))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<
+        SPI: embedded_hal_async::spi::SpiDevice,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, crate::Async, G, BUF>
+{
+    /// Run a commissioning test sequence, holding each step for `step_ms` milliseconds.
+    ///
+    /// See the blocking [`self_test`](P10Led::self_test) for the sequence.
+    pub async fn self_test(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+        step_ms: u32,
+    ) -> Result<(), Error> {
+        self.run_self_test(delay, step_ms).await
+    }
+
+    /// Drive all pixels on at a capped duty for verifying PSU sizing.
+    ///
+    /// See the blocking [`burn_test`](P10Led::burn_test).
+    pub async fn burn_test(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+        test: BurnTest,
+        report: impl FnMut(u32),
+    ) -> Result<u32, Error> {
+        self.run_burn_test(delay, test, report).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock::{driver, NoDelay},
+        ChainOrder, Rotation,
+    };
+
+    #[test]
+    fn panel_overlay_ignores_rotation_and_mirroring() {
+        let (mut upright, upright_spi) = driver::<2, 2>();
+        let (mut turned, turned_spi) = driver::<2, 2>();
+        turned.set_rotation(Rotation::Deg90);
+        turned.set_mirror(true, false);
+
+        upright.draw_panel_overlay();
+        turned.draw_panel_overlay();
+        upright.update().unwrap();
+        turned.update().unwrap();
+        assert_eq!(upright_spi.take(), turned_spi.take());
+    }
+
+    #[test]
+    fn panel_overlay_shows_the_chain_index() {
+        // Every panel shows its own index, so the data shifted into the chain is the same for
+        // any order.
+        let (mut straight, straight_spi) = driver::<2, 2>();
+        let (mut zigzag, zigzag_spi) = driver::<2, 2>();
+        zigzag.set_chain_order(ChainOrder::Serpentine);
+
+        straight.draw_panel_overlay();
+        zigzag.draw_panel_overlay();
+        straight.update().unwrap();
+        zigzag.update().unwrap();
+        assert_eq!(straight_spi.take(), zigzag_spi.take());
+    }
+
+    #[test]
+    fn self_test_shows_its_patterns_while_double_buffered() {
+        let (mut plain, plain_spi) = driver::<2, 1>();
+        let (mut double, double_spi) = driver::<2, 1>();
+        double.set_double_buffered(true);
+        double.set_pixel(5, 3, true);
+
+        plain.self_test(&mut NoDelay, 1).unwrap();
+        double.self_test(&mut NoDelay, 1).unwrap();
+        assert_eq!(plain_spi.take(), double_spi.take());
+
+        // The pending drawing is still in the back buffer and the displayed one cleared.
+        double.update().unwrap();
+        assert!(double_spi.take().iter().all(|&byte| byte == 0xff));
+        double.swap_buffers();
+        double.update().unwrap();
+        plain.set_pixel(5, 3, true);
+        plain.update().unwrap();
+        assert_eq!(plain_spi.take(), double_spi.take());
+    }
+}
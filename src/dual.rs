@@ -0,0 +1,358 @@
+//! Driver for dual-color HUB12 panels with separate red and green data lines.
+//!
+//! Both colors share the clock, latch, enable and row address lines of a mono panel, so
+//! each scan row clocks out a red and a green plane side by side through a [`PlaneBus`].
+//! A pixel with both LEDs lit shows amber.
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::{raw::RawU2, PixelColor},
+    Pixel,
+};
+use embedded_hal::digital::{OutputPin, PinState};
+
+use crate::{
+    AddressPins, Blocking, Buffers, Config, EnableLine, Error, Frame, NoBus, NoLatch, NoPin,
+    P10Led, Panel32x16, PanelGeometry, RowSelector, BLANK,
+};
+
+/// Color of a dual-color pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DualColor {
+    #[default]
+    Off,
+    Red,
+    Green,
+    /// Red and green lit together.
+    Amber,
+}
+
+impl DualColor {
+    pub const fn red(self) -> bool {
+        matches!(self, Self::Red | Self::Amber)
+    }
+
+    pub const fn green(self) -> bool {
+        matches!(self, Self::Green | Self::Amber)
+    }
+
+    const fn from_planes(red: bool, green: bool) -> Self {
+        match (red, green) {
+            (false, false) => Self::Off,
+            (true, false) => Self::Red,
+            (false, true) => Self::Green,
+            (true, true) => Self::Amber,
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl PixelColor for DualColor {
+    type Raw = RawU2;
+}
+
+/// Red and green data lines clocked together, MSB of each byte first.
+///
+/// `red[n]` and `green[n]` go out on the same clock edges. Buses with two data lanes, such as
+/// a dual SPI or a PIO, can implement it directly; [`BitBangPlanes`] drives GPIOs.
+pub trait PlaneBus {
+    fn write_planes(&mut self, red: &[u8], green: &[u8]) -> Result<(), Error>;
+}
+
+/// [`PlaneBus`] bit-banged on the clock and the two data pins, data sampled on the rising edge.
+pub struct BitBangPlanes<CLK, DR, DG> {
+    clock: CLK,
+    red: DR,
+    green: DG,
+}
+
+impl<CLK: OutputPin, DR: OutputPin, DG: OutputPin> BitBangPlanes<CLK, DR, DG> {
+    pub fn new(clock: CLK, red: DR, green: DG) -> Self {
+        Self { clock, red, green }
+    }
+
+    pub fn release(self) -> (CLK, DR, DG) {
+        (self.clock, self.red, self.green)
+    }
+}
+
+impl<CLK: OutputPin, DR: OutputPin, DG: OutputPin> PlaneBus for BitBangPlanes<CLK, DR, DG> {
+    fn write_planes(&mut self, red: &[u8], green: &[u8]) -> Result<(), Error> {
+        for (&r, &g) in red.iter().zip(green) {
+            for bit in (0..8).rev() {
+                self.red
+                    .set_state(PinState::from(r & (1 << bit) != 0))
+                    .map_err(Error::digital)?;
+                self.green
+                    .set_state(PinState::from(g & (1 << bit) != 0))
+                    .map_err(Error::digital)?;
+                self.clock.set_high().map_err(Error::digital)?;
+                self.clock.set_low().map_err(Error::digital)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Row selector of the green plane, whose rows are driven by the red one.
+type GreenRows = AddressPins<NoPin, NoPin, NoPin, NoPin, 4>;
+
+/// Driver for a `PX` x `PY` chain of dual-color panels with geometry `G`.
+///
+/// Each color is a mono [`P10Led`] framebuffer with the same options, so drawing is mapped
+/// and scanned like on a mono chain. The red driver owns the enable, row and latch lines:
+/// its [`EnablePolarity`](crate::EnablePolarity) and brightness, also through a
+/// [`PwmEnable`](crate::PwmEnable), apply to both colors.
+pub struct DualP10<
+    BUS,
+    E: EnableLine,
+    R: RowSelector,
+    L: OutputPin,
+    const PX: usize = 1,
+    const PY: usize = 1,
+    G: PanelGeometry = Panel32x16,
+> {
+    bus: BUS,
+    red: P10Led<NoBus, E, R, L, PX, PY, Blocking, G>,
+    green: P10Led<NoBus, NoPin, GreenRows, NoLatch, PX, PY, Blocking, G>,
+}
+
+impl<
+        BUS: PlaneBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > DualP10<BUS, E, R, L, PX, PY, G>
+{
+    pub const WIDTH: usize = PX * G::WIDTH;
+    pub const HEIGHT: usize = PY * G::HEIGHT;
+
+    /// Create a driver with every pixel off and the defaults of [`P10Led::new`]. `rows`
+    /// selects the scan rows, e.g. [`AddressPins`] on pins A and B.
+    pub fn new(bus: BUS, enable: E, rows: R, latch: L) -> Self {
+        let green_rows = AddressPins {
+            a: NoPin,
+            b: NoPin,
+            c: NoPin,
+            d: NoPin,
+        };
+        Self {
+            bus,
+            red: P10Led::from_parts(NoBus, enable, rows, latch, Buffers::new()),
+            green: P10Led::from_parts(NoBus, NoPin, green_rows, NoLatch, Buffers::new()),
+        }
+    }
+
+    pub fn release(self) -> (BUS, E, R, L) {
+        let P10Led {
+            enable,
+            rows,
+            latch,
+            ..
+        } = self.red;
+        (self.bus, enable, rows, latch)
+    }
+
+    /// Apply the options of `config` to both colors, see [`P10Led::configure`].
+    pub fn configure(&mut self, config: &Config) -> Result<(), Error> {
+        self.red.configure(config)?;
+        self.green.configure(config)
+    }
+
+    /// Set the brightness of both colors, see [`P10Led::set_brightness`].
+    pub fn set_brightness(&mut self, level: u8) -> Result<(), Error> {
+        self.red.set_brightness(level)
+    }
+
+    /// Set the pixel `x`, `y`, ignored outside the display.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: DualColor) {
+        self.red.set_pixel(x, y, color.red());
+        self.green.set_pixel(x, y, color.green());
+    }
+
+    /// Color of the pixel `x`, `y`, [`DualColor::Off`] outside the display.
+    pub fn pixel(&self, x: usize, y: usize) -> DualColor {
+        let (w, h) = self.red.display_dims();
+        if x >= w || y >= h {
+            return DualColor::Off;
+        }
+        let (x, y) = self.red.to_layout(x, y);
+        DualColor::from_planes(self.red.pixel(x, y), self.green.pixel(x, y))
+    }
+
+    /// Set every pixel to `color`.
+    pub fn fill(&mut self, color: DualColor) {
+        self.red.fill(color.red());
+        self.green.fill(color.green());
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(DualColor::Off);
+    }
+
+    /// Replace both colors with prerendered mono images, see [`P10Led::load_frame`].
+    pub fn load_planes(&mut self, red: &Frame<PX, PY, G>, green: &Frame<PX, PY, G>) {
+        self.red.load_frame(red);
+        self.green.load_frame(green);
+    }
+
+    /// Clock both planes of the current scan row out, blanking the panels that skip this
+    /// refresh like the mono driver.
+    fn send_row(&mut self) -> Result<(), Error> {
+        self.red.fill_cache();
+        self.green.fill_cache();
+        let row = self.red.scan_row as usize;
+        let red = self.red.scan_data(row);
+        let green = self.green.scan_data(row);
+        if !self.red.skips_refreshes() {
+            return self.bus.write_planes(red, green);
+        }
+        let chunk_len = self.red.panel_scan_bytes();
+        let chunks = red.chunks(chunk_len).zip(green.chunks(chunk_len));
+        for (panel, (red, green)) in chunks.enumerate() {
+            if self.red.panel_visible(panel) {
+                self.bus.write_planes(red, green)?;
+            } else {
+                let blank = &BLANK[..red.len()];
+                self.bus.write_planes(blank, blank)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Clock both planes out for all scan rows, then blank the display. Call it
+    /// continuously like [`P10Led::update`].
+    pub fn update(&mut self) -> Result<(), Error> {
+        for _ in 0..self.red.scan_rows() {
+            self.send_row()?;
+            self.red.next_row()?;
+            self.green.next_row()?;
+        }
+        self.red.set_output(false)?;
+        for _ in 0..self.red.panels() {
+            let blank = &BLANK[..self.red.panel_scan_bytes()];
+            self.bus.write_planes(blank, blank)?;
+        }
+        self.red.latch.set_high().map_err(Error::digital)?;
+        self.red.latch.set_low().map_err(Error::digital)?;
+        self.red.count_refresh();
+        self.green.count_refresh();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<
+        BUS: PlaneBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > DrawTarget for DualP10<BUS, E, R, L, PX, PY, G>
+{
+    type Color = DualColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if pos.x >= 0 && pos.y >= 0 {
+                self.set_pixel(pos.x as _, pos.y as _, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(color);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<
+        BUS: PlaneBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > OriginDimensions for DualP10<BUS, E, R, L, PX, PY, G>
+{
+    fn size(&self) -> Size {
+        self.red.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{
+        mock::{driver, Pin},
+        ChainOrder, ScanMode,
+    };
+
+    /// [`PlaneBus`] keeping every byte of both planes.
+    #[derive(Default)]
+    struct Planes {
+        red: Vec<u8>,
+        green: Vec<u8>,
+    }
+
+    impl PlaneBus for Planes {
+        fn write_planes(&mut self, red: &[u8], green: &[u8]) -> Result<(), Error> {
+            self.red.extend_from_slice(red);
+            self.green.extend_from_slice(green);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn colors_are_scanned_like_mono_drivers() {
+        let config = Config::new()
+            .with_chain_order(ChainOrder::Serpentine)
+            .with_scan_mode(ScanMode::Half);
+        let rows = AddressPins::new(Pin, Pin);
+        let mut dual = DualP10::<_, _, _, _, 2, 2>::new(Planes::default(), Pin, rows, Pin);
+        let (mut red, red_spi) = driver::<2, 2>();
+        let (mut green, green_spi) = driver::<2, 2>();
+        for display in [&mut red, &mut green] {
+            display.configure(&config).unwrap();
+        }
+        dual.configure(&config).unwrap();
+        let pixels = [
+            (0, 0, DualColor::Red),
+            (40, 20, DualColor::Green),
+            (63, 31, DualColor::Amber),
+        ];
+        for (x, y, color) in pixels {
+            dual.set_pixel(x, y, color);
+            red.set_pixel(x, y, color.red());
+            green.set_pixel(x, y, color.green());
+        }
+        assert_eq!(dual.pixel(40, 20), DualColor::Green);
+
+        dual.update().unwrap();
+        red.update().unwrap();
+        green.update().unwrap();
+        // The mono drivers send the first row once more before the blank data.
+        let row_len = red.panels() * red.panel_scan_bytes();
+        let len = red.scan_rows() as usize * row_len;
+        let (red_data, green_data) = (red_spi.take(), green_spi.take());
+        assert_eq!(dual.bus.red[..len], red_data[..len]);
+        assert_eq!(dual.bus.green[..len], green_data[..len]);
+        assert_eq!(dual.bus.red[len..], red_data[len + row_len..]);
+    }
+}
@@ -0,0 +1,162 @@
+//! Visual effects for binary panels.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Pixel,
+};
+
+/// Number of dither levels, level `DITHER_LEVELS` shows everything.
+pub const DITHER_LEVELS: u8 = 16;
+
+/// 4x4 Bayer matrix of reveal thresholds.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+fn revealed(pos: Point, level: u8) -> bool {
+    BAYER[pos.y.rem_euclid(4) as usize][pos.x.rem_euclid(4) as usize] < level
+}
+
+/// Draw target showing only a dither pattern share of the lit pixels drawn through it,
+/// the rest are drawn off. Level `0` hides all, [`DITHER_LEVELS`] shows all.
+pub struct Dithered<'a, T> {
+    target: &'a mut T,
+    level: u8,
+}
+
+impl<'a, T: DrawTarget<Color = BinaryColor>> Dithered<'a, T> {
+    pub fn new(target: &'a mut T, level: u8) -> Self {
+        Self {
+            target,
+            level: level.min(DITHER_LEVELS),
+        }
+    }
+}
+
+impl<T: Dimensions> Dimensions for Dithered<'_, T> {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<T: DrawTarget<Color = BinaryColor>> DrawTarget for Dithered<'_, T> {
+    type Color = BinaryColor;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let level = self.level;
+        self.target
+            .draw_iter(pixels.into_iter().map(|Pixel(pos, color)| {
+                if color.is_on() && !revealed(pos, level) {
+                    Pixel(pos, BinaryColor::Off)
+                } else {
+                    Pixel(pos, color)
+                }
+            }))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FadeDirection {
+    #[default]
+    In,
+    Out,
+}
+
+/// Progressive dither fade over a number of frames, giving a smooth appearance on a binary
+/// panel without grayscale.
+///
+/// Redraw the content through [`Fade::apply`] every frame and call [`Fade::tick`] after it.
+#[derive(Clone, Copy, Debug)]
+pub struct Fade {
+    frames: u16,
+    frame: u16,
+    direction: FadeDirection,
+}
+
+impl Fade {
+    pub fn new(frames: u16, direction: FadeDirection) -> Self {
+        Self {
+            frames: frames.max(1),
+            frame: 0,
+            direction,
+        }
+    }
+
+    /// Advance by one frame. Returns `true` once the fade has finished.
+    pub fn tick(&mut self) -> bool {
+        self.frame = (self.frame + 1).min(self.frames);
+        self.is_done()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frame == self.frames
+    }
+
+    /// Dither level of the current frame.
+    pub fn level(&self) -> u8 {
+        let level = (self.frame as u32 * DITHER_LEVELS as u32 / self.frames as u32) as u8;
+        match self.direction {
+            FadeDirection::In => level,
+            FadeDirection::Out => DITHER_LEVELS - level,
+        }
+    }
+
+    /// Draw target applying the current fade level to `target`.
+    pub fn apply<'a, T: DrawTarget<Color = BinaryColor>>(
+        &self,
+        target: &'a mut T,
+    ) -> Dithered<'a, T> {
+        Dithered::new(target, self.level())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics_core::geometry::Size;
+
+    use super::*;
+    use crate::Frame;
+
+    #[test]
+    fn bayer_matrix_holds_every_threshold_once() {
+        let mut thresholds = BAYER.as_flattened().to_vec();
+        thresholds.sort_unstable();
+        assert!(thresholds.iter().copied().eq(0..DITHER_LEVELS));
+    }
+
+    #[test]
+    fn dither_level_reveals_that_share_of_pixels() {
+        let block = Rectangle::new(Point::new(4, 8), Size::new(4, 4));
+        for level in 0..=DITHER_LEVELS {
+            let mut frame = Frame::<1, 1>::new();
+            let Ok(()) = Dithered::new(&mut frame, level).fill_solid(&block, BinaryColor::On);
+            let lit = (4..8)
+                .flat_map(|x| (8..12).map(move |y| (x, y)))
+                .filter(|&(x, y)| frame.get(x, y))
+                .count();
+            assert_eq!(lit, level as usize);
+        }
+    }
+
+    #[test]
+    fn fade_steps_through_the_levels() {
+        let mut fade = Fade::new(4, FadeDirection::In);
+        let mut levels = [0; 5];
+        for level in &mut levels {
+            *level = fade.level();
+            fade.tick();
+        }
+        assert_eq!(levels, [0, 4, 8, 12, 16]);
+        assert!(fade.is_done());
+
+        let mut fade = Fade::new(4, FadeDirection::Out);
+        assert_eq!(fade.level(), DITHER_LEVELS);
+        while !fade.tick() {}
+        assert_eq!(fade.level(), 0);
+    }
+}
@@ -0,0 +1,349 @@
+//! Bitmap fonts for the text renderer.
+
+#[cfg(feature = "graphics")]
+pub mod external;
+
+/// Fixed-width bitmap font of up to 8 pixels height.
+///
+/// Glyphs are stored column by column, `width` bytes per glyph, with the top pixel in bit 0,
+/// for the contiguous character range starting at `first_char`.
+#[derive(Clone, Copy, Debug)]
+pub struct Font {
+    pub glyphs: &'static [u8],
+    pub first_char: char,
+    pub width: u8,
+    pub height: u8,
+    /// Blank columns between glyphs.
+    pub spacing: u8,
+}
+
+impl Font {
+    /// Columns of the glyph for `c`, `None` if the font does not cover it.
+    ///
+    /// Lowercase ASCII letters fall back to uppercase for fonts without them.
+    pub fn glyph(&self, c: char) -> Option<&'static [u8]> {
+        self.glyph_exact(c)
+            .or_else(|| self.glyph_exact(c.to_ascii_uppercase()))
+    }
+
+    fn glyph_exact(&self, c: char) -> Option<&'static [u8]> {
+        let index = (c as u32).checked_sub(self.first_char as u32)? as usize;
+        let width = self.width as usize;
+        self.glyphs.get(index * width..(index + 1) * width)
+    }
+
+    /// Horizontal distance between the origins of two neighbouring glyphs.
+    pub fn advance(&self) -> u32 {
+        self.width as u32 + self.spacing as u32
+    }
+
+    /// Width of `text` in pixels, without trailing spacing.
+    pub fn text_width(&self, text: &str) -> u32 {
+        match text.chars().count() as u32 {
+            0 => 0,
+            n => n * self.advance() - self.spacing as u32,
+        }
+    }
+}
+
+/// Font used by [`p10_print!`](crate::p10_print).
+pub const DEFAULT: Font = CONDENSED_4X7;
+
+/// Condensed 4x7 font covering `' '..='Z'`, fitting 6 characters on a single panel.
+///
+/// Meant for price and stock tickers. Lowercase letters render as uppercase.
+pub const CONDENSED_4X7: Font = Font {
+    glyphs: &GLYPHS_4X7,
+    first_char: ' ',
+    width: 4,
+    height: 7,
+    spacing: 1,
+};
+
+#[rustfmt::skip]
+const GLYPHS_4X7: [u8; 236] = [
+    0x00, 0x00, 0x00, 0x00, // ' '
+    0x00, 0x5f, 0x00, 0x00, // '!'
+    0x03, 0x00, 0x03, 0x00, // '"'
+    0x3e, 0x14, 0x3e, 0x14, // '#'
+    0x24, 0x6b, 0x2a, 0x12, // '$'
+    0x33, 0x0b, 0x64, 0x62, // '%'
+    0x36, 0x49, 0x56, 0x30, // '&'
+    0x00, 0x03, 0x00, 0x00, // '\''
+    0x1c, 0x22, 0x41, 0x00, // '('
+    0x00, 0x41, 0x22, 0x1c, // ')'
+    0x2a, 0x1c, 0x1c, 0x2a, // '*'
+    0x08, 0x3e, 0x08, 0x00, // '+'
+    0x00, 0x50, 0x30, 0x00, // ','
+    0x08, 0x08, 0x08, 0x00, // '-'
+    0x00, 0x60, 0x60, 0x00, // '.'
+    0x30, 0x08, 0x04, 0x03, // '/'
+    0x3e, 0x49, 0x45, 0x3e, // '0'
+    0x42, 0x7f, 0x40, 0x00, // '1'
+    0x62, 0x51, 0x49, 0x46, // '2'
+    0x22, 0x49, 0x49, 0x36, // '3'
+    0x1c, 0x12, 0x7f, 0x10, // '4'
+    0x27, 0x45, 0x45, 0x39, // '5'
+    0x3e, 0x49, 0x49, 0x30, // '6'
+    0x01, 0x71, 0x0d, 0x03, // '7'
+    0x36, 0x49, 0x49, 0x36, // '8'
+    0x06, 0x49, 0x49, 0x3e, // '9'
+    0x00, 0x36, 0x36, 0x00, // ':'
+    0x00, 0x56, 0x36, 0x00, // ';'
+    0x08, 0x14, 0x22, 0x41, // '<'
+    0x14, 0x14, 0x14, 0x14, // '='
+    0x41, 0x22, 0x14, 0x08, // '>'
+    0x02, 0x51, 0x09, 0x06, // '?'
+    0x3e, 0x41, 0x5d, 0x4e, // '@'
+    0x7e, 0x09, 0x09, 0x7e, // 'A'
+    0x7f, 0x49, 0x49, 0x36, // 'B'
+    0x3e, 0x41, 0x41, 0x22, // 'C'
+    0x7f, 0x41, 0x41, 0x3e, // 'D'
+    0x7f, 0x49, 0x49, 0x41, // 'E'
+    0x7f, 0x09, 0x09, 0x01, // 'F'
+    0x3e, 0x41, 0x49, 0x7a, // 'G'
+    0x7f, 0x08, 0x08, 0x7f, // 'H'
+    0x41, 0x7f, 0x41, 0x00, // 'I'
+    0x20, 0x40, 0x41, 0x3f, // 'J'
+    0x7f, 0x08, 0x14, 0x63, // 'K'
+    0x7f, 0x40, 0x40, 0x40, // 'L'
+    0x7f, 0x06, 0x06, 0x7f, // 'M'
+    0x7f, 0x06, 0x18, 0x7f, // 'N'
+    0x3e, 0x41, 0x41, 0x3e, // 'O'
+    0x7f, 0x09, 0x09, 0x06, // 'P'
+    0x3e, 0x41, 0x51, 0x7e, // 'Q'
+    0x7f, 0x09, 0x19, 0x66, // 'R'
+    0x26, 0x49, 0x49, 0x32, // 'S'
+    0x01, 0x7f, 0x01, 0x00, // 'T'
+    0x3f, 0x40, 0x40, 0x3f, // 'U'
+    0x1f, 0x60, 0x60, 0x1f, // 'V'
+    0x7f, 0x30, 0x30, 0x7f, // 'W'
+    0x63, 0x1c, 0x1c, 0x63, // 'X'
+    0x07, 0x78, 0x07, 0x00, // 'Y'
+    0x71, 0x49, 0x45, 0x43, // 'Z'
+];
+
+/// Classic 5x7 font covering printable ASCII `' '..='~'`, used by
+/// [`P10Led::draw_text`](crate::P10Led::draw_text).
+///
+/// Readable at a distance, with proper lowercase letters; 5 characters fit on a single panel.
+pub const FONT_5X7: Font = Font {
+    glyphs: &GLYPHS_5X7,
+    first_char: ' ',
+    width: 5,
+    height: 7,
+    spacing: 1,
+};
+
+#[rustfmt::skip]
+const GLYPHS_5X7: [u8; 475] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, // ' '
+    0x00, 0x00, 0x5f, 0x00, 0x00, // '!'
+    0x00, 0x07, 0x00, 0x07, 0x00, // '"'
+    0x14, 0x7f, 0x14, 0x7f, 0x14, // '#'
+    0x24, 0x2a, 0x7f, 0x2a, 0x12, // '$'
+    0x23, 0x13, 0x08, 0x64, 0x62, // '%'
+    0x36, 0x49, 0x55, 0x22, 0x50, // '&'
+    0x00, 0x05, 0x03, 0x00, 0x00, // '\''
+    0x00, 0x1c, 0x22, 0x41, 0x00, // '('
+    0x00, 0x41, 0x22, 0x1c, 0x00, // ')'
+    0x14, 0x08, 0x3e, 0x08, 0x14, // '*'
+    0x08, 0x08, 0x3e, 0x08, 0x08, // '+'
+    0x00, 0x50, 0x30, 0x00, 0x00, // ','
+    0x08, 0x08, 0x08, 0x08, 0x08, // '-'
+    0x00, 0x60, 0x60, 0x00, 0x00, // '.'
+    0x20, 0x10, 0x08, 0x04, 0x02, // '/'
+    0x3e, 0x51, 0x49, 0x45, 0x3e, // '0'
+    0x00, 0x42, 0x7f, 0x40, 0x00, // '1'
+    0x42, 0x61, 0x51, 0x49, 0x46, // '2'
+    0x21, 0x41, 0x45, 0x4b, 0x31, // '3'
+    0x18, 0x14, 0x12, 0x7f, 0x10, // '4'
+    0x27, 0x45, 0x45, 0x45, 0x39, // '5'
+    0x3c, 0x4a, 0x49, 0x49, 0x30, // '6'
+    0x01, 0x71, 0x09, 0x05, 0x03, // '7'
+    0x36, 0x49, 0x49, 0x49, 0x36, // '8'
+    0x06, 0x49, 0x49, 0x29, 0x1e, // '9'
+    0x00, 0x36, 0x36, 0x00, 0x00, // ':'
+    0x00, 0x56, 0x36, 0x00, 0x00, // ';'
+    0x08, 0x14, 0x22, 0x41, 0x00, // '<'
+    0x14, 0x14, 0x14, 0x14, 0x14, // '='
+    0x00, 0x41, 0x22, 0x14, 0x08, // '>'
+    0x02, 0x01, 0x51, 0x09, 0x06, // '?'
+    0x32, 0x49, 0x79, 0x41, 0x3e, // '@'
+    0x7e, 0x11, 0x11, 0x11, 0x7e, // 'A'
+    0x7f, 0x49, 0x49, 0x49, 0x36, // 'B'
+    0x3e, 0x41, 0x41, 0x41, 0x22, // 'C'
+    0x7f, 0x41, 0x41, 0x22, 0x1c, // 'D'
+    0x7f, 0x49, 0x49, 0x49, 0x41, // 'E'
+    0x7f, 0x09, 0x09, 0x09, 0x01, // 'F'
+    0x3e, 0x41, 0x49, 0x49, 0x7a, // 'G'
+    0x7f, 0x08, 0x08, 0x08, 0x7f, // 'H'
+    0x00, 0x41, 0x7f, 0x41, 0x00, // 'I'
+    0x20, 0x40, 0x41, 0x3f, 0x01, // 'J'
+    0x7f, 0x08, 0x14, 0x22, 0x41, // 'K'
+    0x7f, 0x40, 0x40, 0x40, 0x40, // 'L'
+    0x7f, 0x02, 0x0c, 0x02, 0x7f, // 'M'
+    0x7f, 0x04, 0x08, 0x10, 0x7f, // 'N'
+    0x3e, 0x41, 0x41, 0x41, 0x3e, // 'O'
+    0x7f, 0x09, 0x09, 0x09, 0x06, // 'P'
+    0x3e, 0x41, 0x51, 0x21, 0x5e, // 'Q'
+    0x7f, 0x09, 0x19, 0x29, 0x46, // 'R'
+    0x46, 0x49, 0x49, 0x49, 0x31, // 'S'
+    0x01, 0x01, 0x7f, 0x01, 0x01, // 'T'
+    0x3f, 0x40, 0x40, 0x40, 0x3f, // 'U'
+    0x1f, 0x20, 0x40, 0x20, 0x1f, // 'V'
+    0x3f, 0x40, 0x38, 0x40, 0x3f, // 'W'
+    0x63, 0x14, 0x08, 0x14, 0x63, // 'X'
+    0x07, 0x08, 0x70, 0x08, 0x07, // 'Y'
+    0x61, 0x51, 0x49, 0x45, 0x43, // 'Z'
+    0x00, 0x7f, 0x41, 0x41, 0x00, // '['
+    0x02, 0x04, 0x08, 0x10, 0x20, // '\\'
+    0x00, 0x41, 0x41, 0x7f, 0x00, // ']'
+    0x04, 0x02, 0x01, 0x02, 0x04, // '^'
+    0x40, 0x40, 0x40, 0x40, 0x40, // '_'
+    0x00, 0x01, 0x02, 0x04, 0x00, // '`'
+    0x20, 0x54, 0x54, 0x54, 0x78, // 'a'
+    0x7f, 0x48, 0x44, 0x44, 0x38, // 'b'
+    0x38, 0x44, 0x44, 0x44, 0x20, // 'c'
+    0x38, 0x44, 0x44, 0x48, 0x7f, // 'd'
+    0x38, 0x54, 0x54, 0x54, 0x18, // 'e'
+    0x08, 0x7e, 0x09, 0x01, 0x02, // 'f'
+    0x0c, 0x52, 0x52, 0x52, 0x3e, // 'g'
+    0x7f, 0x08, 0x04, 0x04, 0x78, // 'h'
+    0x00, 0x44, 0x7d, 0x40, 0x00, // 'i'
+    0x20, 0x40, 0x44, 0x3d, 0x00, // 'j'
+    0x7f, 0x10, 0x28, 0x44, 0x00, // 'k'
+    0x00, 0x41, 0x7f, 0x40, 0x00, // 'l'
+    0x7c, 0x04, 0x18, 0x04, 0x78, // 'm'
+    0x7c, 0x08, 0x04, 0x04, 0x78, // 'n'
+    0x38, 0x44, 0x44, 0x44, 0x38, // 'o'
+    0x7c, 0x14, 0x14, 0x14, 0x08, // 'p'
+    0x08, 0x14, 0x14, 0x18, 0x7c, // 'q'
+    0x7c, 0x08, 0x04, 0x04, 0x08, // 'r'
+    0x48, 0x54, 0x54, 0x54, 0x20, // 's'
+    0x04, 0x3f, 0x44, 0x40, 0x20, // 't'
+    0x3c, 0x40, 0x40, 0x20, 0x7c, // 'u'
+    0x1c, 0x20, 0x40, 0x20, 0x1c, // 'v'
+    0x3c, 0x40, 0x30, 0x40, 0x3c, // 'w'
+    0x44, 0x28, 0x10, 0x28, 0x44, // 'x'
+    0x0c, 0x50, 0x50, 0x50, 0x3c, // 'y'
+    0x44, 0x64, 0x54, 0x4c, 0x44, // 'z'
+    0x00, 0x08, 0x36, 0x41, 0x00, // '{'
+    0x00, 0x00, 0x7f, 0x00, 0x00, // '|'
+    0x00, 0x41, 0x36, 0x08, 0x00, // '}'
+    0x08, 0x04, 0x08, 0x10, 0x08, // '~'
+];
+
+/// Tiny 3x5 font covering `' '..='Z'`, small enough for two lines on a 16 pixel panel.
+///
+/// Lowercase letters render as uppercase.
+pub const TINY_3X5: Font = Font {
+    glyphs: &GLYPHS_3X5,
+    first_char: ' ',
+    width: 3,
+    height: 5,
+    spacing: 1,
+};
+
+#[rustfmt::skip]
+const GLYPHS_3X5: [u8; 177] = [
+    0x00, 0x00, 0x00, // ' '
+    0x00, 0x17, 0x00, // '!'
+    0x03, 0x00, 0x03, // '"'
+    0x1f, 0x0a, 0x1f, // '#'
+    0x12, 0x1f, 0x09, // '$'
+    0x19, 0x04, 0x13, // '%'
+    0x0a, 0x15, 0x1a, // '&'
+    0x00, 0x03, 0x00, // '\''
+    0x00, 0x0e, 0x11, // '('
+    0x11, 0x0e, 0x00, // ')'
+    0x0a, 0x04, 0x0a, // '*'
+    0x04, 0x0e, 0x04, // '+'
+    0x10, 0x08, 0x00, // ','
+    0x04, 0x04, 0x04, // '-'
+    0x00, 0x10, 0x00, // '.'
+    0x18, 0x04, 0x03, // '/'
+    0x1f, 0x11, 0x1f, // '0'
+    0x12, 0x1f, 0x10, // '1'
+    0x1d, 0x15, 0x17, // '2'
+    0x15, 0x15, 0x1f, // '3'
+    0x07, 0x04, 0x1f, // '4'
+    0x17, 0x15, 0x1d, // '5'
+    0x1f, 0x15, 0x1d, // '6'
+    0x01, 0x01, 0x1f, // '7'
+    0x1f, 0x15, 0x1f, // '8'
+    0x17, 0x15, 0x1f, // '9'
+    0x00, 0x0a, 0x00, // ':'
+    0x10, 0x0a, 0x00, // ';'
+    0x04, 0x0a, 0x11, // '<'
+    0x0a, 0x0a, 0x0a, // '='
+    0x11, 0x0a, 0x04, // '>'
+    0x01, 0x15, 0x02, // '?'
+    0x0e, 0x15, 0x16, // '@'
+    0x1e, 0x05, 0x1e, // 'A'
+    0x1f, 0x15, 0x0a, // 'B'
+    0x0e, 0x11, 0x11, // 'C'
+    0x1f, 0x11, 0x0e, // 'D'
+    0x1f, 0x15, 0x11, // 'E'
+    0x1f, 0x05, 0x01, // 'F'
+    0x0e, 0x11, 0x1d, // 'G'
+    0x1f, 0x04, 0x1f, // 'H'
+    0x11, 0x1f, 0x11, // 'I'
+    0x08, 0x10, 0x0f, // 'J'
+    0x1f, 0x04, 0x1b, // 'K'
+    0x1f, 0x10, 0x10, // 'L'
+    0x1f, 0x06, 0x1f, // 'M'
+    0x1f, 0x01, 0x1e, // 'N'
+    0x0e, 0x11, 0x0e, // 'O'
+    0x1f, 0x05, 0x02, // 'P'
+    0x0e, 0x19, 0x16, // 'Q'
+    0x1f, 0x05, 0x1a, // 'R'
+    0x12, 0x15, 0x09, // 'S'
+    0x01, 0x1f, 0x01, // 'T'
+    0x0f, 0x10, 0x1f, // 'U'
+    0x07, 0x18, 0x07, // 'V'
+    0x1f, 0x0c, 0x1f, // 'W'
+    0x1b, 0x04, 0x1b, // 'X'
+    0x03, 0x1c, 0x03, // 'Y'
+    0x19, 0x15, 0x13, // 'Z'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONTS: [Font; 3] = [CONDENSED_4X7, FONT_5X7, TINY_3X5];
+
+    #[test]
+    fn glyphs_fit_the_cell() {
+        for font in FONTS {
+            assert_eq!(font.glyphs.len() % font.width as usize, 0);
+            assert!(font.glyphs.iter().all(|&bits| bits >> font.height == 0));
+        }
+    }
+
+    #[test]
+    fn glyphs_are_looked_up_from_the_first_char() {
+        assert_eq!(TINY_3X5.glyph(' '), Some(&[0x00, 0x00, 0x00][..]));
+        assert_eq!(TINY_3X5.glyph('1'), Some(&[0x12, 0x1f, 0x10][..]));
+        assert_eq!(TINY_3X5.glyph('\n'), None);
+        assert_eq!(TINY_3X5.glyph('~'), None);
+    }
+
+    #[test]
+    fn lowercase_falls_back_to_uppercase() {
+        assert_eq!(CONDENSED_4X7.glyph('a'), CONDENSED_4X7.glyph('A'));
+        assert!(FONT_5X7.glyph('a').is_some());
+        assert_ne!(FONT_5X7.glyph('a'), FONT_5X7.glyph('A'));
+    }
+
+    #[test]
+    fn text_width_leaves_out_the_trailing_spacing() {
+        assert_eq!(TINY_3X5.advance(), 4);
+        assert_eq!(TINY_3X5.text_width(""), 0);
+        assert_eq!(TINY_3X5.text_width("1"), 3);
+        assert_eq!(TINY_3X5.text_width("123"), 11);
+        // Characters, not bytes.
+        assert_eq!(FONT_5X7.text_width("é"), 5);
+    }
+}
@@ -0,0 +1,182 @@
+//! Fonts read from external memory at runtime.
+//!
+//! Large fonts, e.g. covering many Unicode blocks, often do not fit the MCU flash. Their
+//! glyphs are fetched on demand from a [`GlyphSource`] such as SPI NOR flash and kept in a
+//! small [`GlyphCache`], since text is usually redrawn every frame.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget, geometry::Point, pixelcolor::BinaryColor, Pixel,
+};
+
+/// Widest and tallest glyph supported, a single panel height.
+pub const MAX_GLYPH_SIZE: usize = 16;
+
+/// Storage glyphs are fetched from.
+pub trait GlyphSource {
+    type Error;
+
+    /// Glyph width and height in pixels, both at most [`MAX_GLYPH_SIZE`].
+    fn size(&self) -> (u8, u8);
+
+    /// Blank columns between glyphs.
+    fn spacing(&self) -> u8 {
+        1
+    }
+
+    /// Fill `columns`, one entry per glyph column with the top pixel in bit 0, with the glyph
+    /// for `c`. Returns `false` if the font does not cover it.
+    fn read_glyph(&mut self, c: char, columns: &mut [u16]) -> Result<bool, Self::Error>;
+}
+
+/// Random access read of external memory, implemented for the flash driver of the board.
+pub trait ReadAt {
+    type Error;
+
+    fn read_at(&mut self, address: u32, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// Fixed-width font for a contiguous character range stored at `base` in external memory.
+///
+/// Each glyph takes `width` columns of two bytes, little-endian with the top pixel in bit 0,
+/// so glyph `n` starts at `base + n * width * 2`.
+#[derive(Clone, Copy, Debug)]
+pub struct FlashFont<M> {
+    pub memory: M,
+    pub base: u32,
+    pub first_char: char,
+    /// Number of glyphs stored.
+    pub count: u32,
+    pub width: u8,
+    pub height: u8,
+}
+
+impl<M: ReadAt> GlyphSource for FlashFont<M> {
+    type Error = M::Error;
+
+    fn size(&self) -> (u8, u8) {
+        (self.width, self.height)
+    }
+
+    fn read_glyph(&mut self, c: char, columns: &mut [u16]) -> Result<bool, Self::Error> {
+        let Some(index) = (c as u32).checked_sub(self.first_char as u32) else {
+            return Ok(false);
+        };
+        if index >= self.count {
+            return Ok(false);
+        }
+        let width = (self.width as usize).min(MAX_GLYPH_SIZE);
+        let mut raw = [0; MAX_GLYPH_SIZE * 2];
+        let raw = &mut raw[..width * 2];
+        self.memory
+            .read_at(self.base + index * width as u32 * 2, raw)?;
+        for (column, bytes) in columns.iter_mut().zip(raw.chunks_exact(2)) {
+            *column = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+        Ok(true)
+    }
+}
+
+/// Failure drawing text from a [`GlyphCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextError<S, D> {
+    /// The glyph source could not be read.
+    Source(S),
+    /// The draw target failed.
+    Draw(D),
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    c: char,
+    /// Whether the source covers `c`; uncovered characters are cached too.
+    found: bool,
+    columns: [u16; MAX_GLYPH_SIZE],
+}
+
+/// Cache of the last `N` glyphs read from `S`, replaced round-robin.
+pub struct GlyphCache<S, const N: usize> {
+    source: S,
+    entries: [Option<Entry>; N],
+    next: usize,
+}
+
+impl<S: GlyphSource, const N: usize> GlyphCache<S, N> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            entries: [None; N],
+            next: 0,
+        }
+    }
+
+    /// Drop all cached glyphs, e.g. after the font in external memory was replaced.
+    pub fn clear(&mut self) {
+        self.entries = [None; N];
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Columns of the glyph for `c`, read from the source unless cached. `None` if the font
+    /// does not cover it.
+    pub fn glyph(&mut self, c: char) -> Result<Option<&[u16]>, S::Error> {
+        let width = (self.source.size().0 as usize).min(MAX_GLYPH_SIZE);
+        let slot = match self
+            .entries
+            .iter()
+            .position(|e| e.is_some_and(|e| e.c == c))
+        {
+            Some(slot) => slot,
+            None => {
+                if N == 0 {
+                    return Ok(None);
+                }
+                let mut entry = Entry {
+                    c,
+                    found: false,
+                    columns: [0; MAX_GLYPH_SIZE],
+                };
+                entry.found = self.source.read_glyph(c, &mut entry.columns[..width])?;
+                let slot = self.next;
+                self.next = (self.next + 1) % N;
+                self.entries[slot] = Some(entry);
+                slot
+            }
+        };
+        Ok(self.entries[slot]
+            .as_ref()
+            .filter(|e| e.found)
+            .map(|e| &e.columns[..width]))
+    }
+
+    /// Draw `text` with its top-left corner at `position`. Returns the position following
+    /// the last glyph.
+    pub fn draw_text<D>(
+        &mut self,
+        target: &mut D,
+        text: &str,
+        position: Point,
+        color: BinaryColor,
+    ) -> Result<Point, TextError<S::Error, D::Error>>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let (width, height) = self.source.size();
+        let advance = width as i32 + self.source.spacing() as i32;
+        let height = (height as u32).min(MAX_GLYPH_SIZE as u32);
+        let mut x0 = position.x;
+        for c in text.chars() {
+            let columns = self.glyph(c).map_err(TextError::Source)?.unwrap_or(&[]);
+            let pixels = columns.iter().enumerate().flat_map(|(col, &bits)| {
+                let x = x0 + col as i32;
+                (0..height)
+                    .filter(move |&row| bits & (1 << row) != 0)
+                    .map(move |row| Pixel(Point::new(x, position.y + row as i32), color))
+            });
+            target.draw_iter(pixels).map_err(TextError::Draw)?;
+            x0 += advance;
+        }
+        Ok(Point::new(x0, position.y))
+    }
+}
@@ -0,0 +1,537 @@
+//! Off-screen frames, a single-slot channel handing them to the driver and a stack of saved
+//! screens.
+
+use core::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    sync::atomic::{AtomicU32, AtomicU8, Ordering},
+};
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+use embedded_hal::digital::OutputPin;
+
+use crate::{BufferStorage, Clock, EnableLine, P10Led, Panel32x16, PanelGeometry, RowSelector};
+
+/// Off-screen 1 bit per pixel image with the size of a `PX` x `PY` chain of `G` panels.
+///
+/// Pixels are stored row-major, so a frame can be rendered without access to the driver
+/// and is only mapped onto the panels by [`P10Led::load_frame`].
+pub struct Frame<const PX: usize = 1, const PY: usize = 1, G = Panel32x16> {
+    /// Row-major bits; nested per panel as array lengths cannot be computed from `PX`, `PY`,
+    /// with room for the 512 pixels of the largest panel.
+    bits: [[[u8; 64]; PX]; PY],
+    _geometry: PhantomData<G>,
+}
+
+impl<const PX: usize, const PY: usize, G> Clone for Frame<PX, PY, G> {
+    fn clone(&self) -> Self {
+        Self {
+            bits: self.bits,
+            _geometry: PhantomData,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.bits = source.bits;
+    }
+}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> Default for Frame<PX, PY, G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> Frame<PX, PY, G> {
+    pub const WIDTH: usize = PX * G::WIDTH;
+    pub const HEIGHT: usize = PY * G::HEIGHT;
+    /// Size of the packed image returned by [`as_bytes`](Self::as_bytes).
+    pub const BYTES: usize = Self::WIDTH * Self::HEIGHT / 8;
+
+    /// Create an all-off frame.
+    pub const fn new() -> Self {
+        Self {
+            bits: [[[0; 64]; PX]; PY],
+            _geometry: PhantomData,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.as_bytes_mut().fill(0);
+    }
+
+    /// Packed image, rows top to bottom with the leftmost pixel in the MSB of each byte.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits.as_flattened().as_flattened()[..Self::BYTES]
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.bits.as_flattened_mut().as_flattened_mut()[..Self::BYTES]
+    }
+
+    /// State of the pixel at `x`, `y`, `false` outside the frame.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        if x >= Self::WIDTH || y >= Self::HEIGHT {
+            return false;
+        }
+        self.as_bytes()[(x + y * Self::WIDTH) / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, on: bool) {
+        if x >= Self::WIDTH || y >= Self::HEIGHT {
+            return;
+        }
+        let byte = &mut self.as_bytes_mut()[(x + y * Self::WIDTH) / 8];
+        if on {
+            *byte |= 0x80 >> (x % 8);
+        } else {
+            *byte &= !(0x80 >> (x % 8));
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> DrawTarget for Frame<PX, PY, G> {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if pos.x >= 0 && pos.y >= 0 {
+                self.set(pos.x as _, pos.y as _, color.is_on());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> OriginDimensions for Frame<PX, PY, G> {
+    fn size(&self) -> Size {
+        Size::new(Self::WIDTH as _, Self::HEIGHT as _)
+    }
+}
+
+/// Screens saved from the framebuffer of a driver, last in first out, e.g. under a menu or
+/// a screensaver to bring back what was shown before.
+pub struct ScreenStack<const N: usize, const PX: usize = 1, const PY: usize = 1, G = Panel32x16> {
+    screens: [Frame<PX, PY, G>; N],
+    depth: usize,
+}
+
+impl<const N: usize, const PX: usize, const PY: usize, G: PanelGeometry> Default
+    for ScreenStack<N, PX, PY, G>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const PX: usize, const PY: usize, G: PanelGeometry> ScreenStack<N, PX, PY, G> {
+    /// Create an empty stack with room for `N` screens.
+    pub const fn new() -> Self {
+        Self {
+            screens: [const { Frame::new() }; N],
+            depth: 0,
+        }
+    }
+
+    /// Number of saved screens.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.depth == N
+    }
+
+    /// Most recently saved screen.
+    pub fn top(&self) -> Option<&Frame<PX, PY, G>> {
+        self.depth.checked_sub(1).map(|top| &self.screens[top])
+    }
+
+    /// Save the framebuffer of `display`. Returns `false` if the stack is full, nothing is
+    /// saved then.
+    pub fn push<SPI, E, R, L, MODE, BUF>(
+        &mut self,
+        display: &P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>,
+    ) -> bool
+    where
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        BUF: BufferStorage<PX, PY>,
+    {
+        let Some(screen) = self.screens.get_mut(self.depth) else {
+            return false;
+        };
+        display.store_frame(screen);
+        self.depth += 1;
+        true
+    }
+
+    /// Load the most recently saved screen back into `display`. Returns `false` if the
+    /// stack is empty, the framebuffer is left alone then.
+    pub fn pop<SPI, E, R, L, MODE, BUF>(
+        &mut self,
+        display: &mut P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>,
+    ) -> bool
+    where
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        BUF: BufferStorage<PX, PY>,
+    {
+        let Some(top) = self.depth.checked_sub(1) else {
+            return false;
+        };
+        display.load_frame(&self.screens[top]);
+        self.depth = top;
+        true
+    }
+
+    /// Drop all saved screens.
+    pub fn clear(&mut self) {
+        self.depth = 0;
+    }
+}
+
+const EMPTY: u8 = 0;
+const FULL: u8 = 1;
+
+/// Single-slot channel passing completed frames from a renderer to the refresh side.
+///
+/// A frame sent while the previous one has not been picked up yet is dropped and counted,
+/// see [`Stats::frames_dropped`]. Only atomic loads and stores are used, so it also works on
+/// cores without compare-and-swap.
+pub struct FrameChannel<const PX: usize = 1, const PY: usize = 1, G = Panel32x16> {
+    state: AtomicU8,
+    dropped: AtomicU32,
+    frame: UnsafeCell<Frame<PX, PY, G>>,
+}
+
+// Safety: the slot is written only by the single `Sender` while `EMPTY` and read only by the
+// single `Receiver` while `FULL`, the state hand-over orders the accesses.
+unsafe impl<const PX: usize, const PY: usize, G> Sync for FrameChannel<PX, PY, G> {}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> Default for FrameChannel<PX, PY, G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> FrameChannel<PX, PY, G> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            dropped: AtomicU32::new(0),
+            frame: UnsafeCell::new(Frame::new()),
+        }
+    }
+
+    /// Split into the sending and receiving ends.
+    pub fn split(&mut self) -> (Sender<'_, PX, PY, G>, Receiver<'_, PX, PY, G>) {
+        (Sender { channel: self }, Receiver { channel: self })
+    }
+}
+
+/// Renderer end of a [`FrameChannel`].
+pub struct Sender<'a, const PX: usize, const PY: usize, G = Panel32x16> {
+    channel: &'a FrameChannel<PX, PY, G>,
+}
+
+impl<const PX: usize, const PY: usize, G> Sender<'_, PX, PY, G> {
+    /// Queue `frame` for display. Returns `false` and counts the frame as dropped if the
+    /// previous one has not been picked up yet.
+    pub fn try_send(&mut self, frame: &Frame<PX, PY, G>) -> bool {
+        let channel = self.channel;
+        if channel.state.load(Ordering::Acquire) != EMPTY {
+            let dropped = channel.dropped.load(Ordering::Relaxed);
+            channel
+                .dropped
+                .store(dropped.wrapping_add(1), Ordering::Relaxed);
+            return false;
+        }
+        // Safety: the receiver does not touch the slot while it is `EMPTY`.
+        unsafe { (*channel.frame.get()).clone_from(frame) };
+        channel.state.store(FULL, Ordering::Release);
+        true
+    }
+}
+
+/// Refresh end of a [`FrameChannel`], consumed by [`P10Led::receive_frame`].
+pub struct Receiver<'a, const PX: usize, const PY: usize, G = Panel32x16> {
+    channel: &'a FrameChannel<PX, PY, G>,
+}
+
+impl<const PX: usize, const PY: usize, G> Receiver<'_, PX, PY, G> {
+    /// Pass the pending frame, if any, to `f` and free the slot.
+    pub fn try_receive(&mut self, f: impl FnOnce(&Frame<PX, PY, G>)) -> bool {
+        let channel = self.channel;
+        if channel.state.load(Ordering::Acquire) != FULL {
+            return false;
+        }
+        // Safety: the sender does not touch the slot while it is `FULL`.
+        f(unsafe { &*channel.frame.get() });
+        channel.state.store(EMPTY, Ordering::Release);
+        true
+    }
+
+    /// Total number of frames dropped by the sender.
+    pub fn dropped(&self) -> u32 {
+        self.channel.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Driver statistics, see [`P10Led::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Completed refresh cycles that shifted frame data; blanking for sleep, diagnostics or
+    /// a new layout and scans while the display is off are not counted.
+    pub refreshes: u32,
+    /// Frames taken over from a [`FrameChannel`].
+    pub frames_received: u32,
+    /// Frames dropped by the renderer because the display had not picked up the previous one.
+    pub frames_dropped: u32,
+    /// Duration of the last scan timed by `update_timed`, in microseconds.
+    pub scan_us: u32,
+    /// Longest scan timed by `update_timed` so far, in microseconds.
+    pub max_scan_us: u32,
+    /// Refreshes per second over the last window of [`P10Led::sample_refresh_rate`].
+    pub refresh_rate_hz: u32,
+}
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Replace the framebuffer with `frame`.
+    ///
+    /// With the stock pixel mapping the frame is copied a panel row at a time, otherwise
+    /// pixel by pixel.
+    pub fn load_frame(&mut self, frame: &Frame<PX, PY, G>) {
+        if self.pixel_remap.is_some() || !self.straight_mapping() {
+            for y in 0..self.height() {
+                for x in 0..self.width() {
+                    self.put_pixel(x, y, frame.get(x, y));
+                }
+            }
+            return;
+        }
+        let span = Self::PANEL_WIDTH / 8;
+        let bytes = frame.as_bytes();
+        for y in 0..self.height() {
+            for col in 0..self.panels_x {
+                let x = col * Self::PANEL_WIDTH;
+                let src = &bytes[(x + y * Frame::<PX, PY, G>::WIDTH) / 8..][..span];
+                let start = self.pixel_to_bitmap_index(x, y);
+                let dst = &mut self.bitmap_mut()[start..start + span];
+                // A set frame bit lights the pixel, a cleared framebuffer bit does.
+                for (dst, &src) in dst.iter_mut().zip(src) {
+                    *dst = !src;
+                }
+            }
+        }
+        self.content_changed = true;
+        if !self.double_buffered {
+            self.dirty = u16::MAX;
+        }
+    }
+
+    /// Copy the framebuffer into `frame`, e.g. to restore it later with
+    /// [`load_frame`](Self::load_frame).
+    pub fn store_frame(&self, frame: &mut Frame<PX, PY, G>) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                frame.set(x, y, self.pixel(x, y));
+            }
+        }
+    }
+
+    /// Load the pending frame of a [`FrameChannel`], if any. Returns whether one was loaded.
+    ///
+    /// Call it before each `update` so the renderer can run at its own pace.
+    pub fn receive_frame(&mut self, rx: &mut Receiver<'_, PX, PY, G>) -> bool {
+        let received = rx.try_receive(|frame| self.load_frame(frame));
+        if received {
+            self.stats.frames_received = self.stats.frames_received.wrapping_add(1);
+        }
+        self.stats.frames_dropped = rx.dropped();
+        received
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Update [`Stats::refresh_rate_hz`] from the refreshes counted since the previous
+    /// window, once at least a second of `clock` time has passed. Call it from the main
+    /// loop to see whether the refresh keeps up however it is driven.
+    pub fn sample_refresh_rate(&mut self, clock: &impl Clock) -> u32 {
+        let now = clock.now_us();
+        let refreshes = self.stats.refreshes;
+        match self.rate_window {
+            Some((start, count)) => {
+                let elapsed = now.wrapping_sub(start);
+                if elapsed >= 1_000_000 {
+                    let done = refreshes.wrapping_sub(count) as u64;
+                    self.stats.refresh_rate_hz = (done * 1_000_000 / elapsed as u64) as u32;
+                    self.rate_window = Some((now, refreshes));
+                }
+            }
+            None => self.rate_window = Some((now, refreshes)),
+        }
+        self.stats.refresh_rate_hz
+    }
+
+    /// Record a scan that took `duration_us`.
+    pub(crate) fn record_scan(&mut self, duration_us: u32) {
+        self.stats.scan_us = duration_us;
+        self.stats.max_scan_us = self.stats.max_scan_us.max(duration_us);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::driver, ChainOrder};
+
+    /// Panel of a quarter-size stock module.
+    struct Panel16x8;
+
+    impl PanelGeometry for Panel16x8 {
+        const WIDTH: usize = 16;
+        const HEIGHT: usize = 8;
+    }
+
+    #[test]
+    fn frame_packs_rows_msb_first() {
+        let mut frame = Frame::<2, 1>::new();
+        frame.set(0, 0, true);
+        frame.set(9, 1, true);
+        frame.set(64, 0, true);
+        assert!(frame.get(0, 0) && frame.get(9, 1));
+        assert!(!frame.get(1, 0) && !frame.get(64, 0));
+        assert_eq!(frame.as_bytes().len(), 128);
+        assert_eq!(
+            &frame.as_bytes()[..10],
+            &[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0x40]
+        );
+        frame.set(0, 0, false);
+        assert_eq!(frame.as_bytes()[0], 0);
+    }
+
+    #[test]
+    fn frame_follows_the_panel_geometry() {
+        type Small = Frame<3, 2, Panel16x8>;
+        assert_eq!((Small::WIDTH, Small::HEIGHT, Small::BYTES), (48, 16, 96));
+        let mut frame = Small::new();
+        frame.set(47, 15, true);
+        assert_eq!(frame.as_bytes().len(), 96);
+        assert_eq!(frame.as_bytes()[95], 0x01);
+    }
+
+    #[test]
+    fn channel_hands_over_one_frame_at_a_time() {
+        let mut channel = FrameChannel::<1, 1>::new();
+        let (mut tx, mut rx) = channel.split();
+        assert!(!rx.try_receive(|_| panic!("nothing was sent")));
+
+        let mut frame = Frame::new();
+        frame.set(3, 4, true);
+        assert!(tx.try_send(&frame));
+        frame.clear();
+        // The slot is still full.
+        assert!(!tx.try_send(&frame));
+        assert_eq!(rx.dropped(), 1);
+
+        let mut lit = false;
+        assert!(rx.try_receive(|frame| lit = frame.get(3, 4)));
+        assert!(lit);
+        assert!(!rx.try_receive(|_| panic!("the frame was taken")));
+        assert!(tx.try_send(&frame));
+    }
+
+    #[test]
+    fn driver_loads_received_frames() {
+        let mut channel = FrameChannel::<2, 1>::new();
+        let (mut tx, mut rx) = channel.split();
+        let (mut display, _) = driver::<2, 1>();
+        let mut frame = Frame::new();
+        frame.set(40, 9, true);
+        tx.try_send(&frame);
+
+        assert!(display.receive_frame(&mut rx));
+        assert!(!display.receive_frame(&mut rx));
+        assert_eq!(display.stats().frames_received, 1);
+        let mut shown = Frame::new();
+        display.store_frame(&mut shown);
+        assert!(shown.get(40, 9));
+    }
+
+    #[test]
+    fn loaded_frames_match_drawing_them() {
+        let mut frame = Frame::<2, 2>::new();
+        for (x, y) in [(0, 0), (9, 3), (40, 15), (63, 16), (33, 31)] {
+            frame.set(x, y, true);
+        }
+        for order in [ChainOrder::RowMajor, ChainOrder::Serpentine] {
+            let (mut loaded, loaded_spi) = driver::<2, 2>();
+            let (mut drawn, drawn_spi) = driver::<2, 2>();
+            loaded.set_chain_order(order);
+            drawn.set_chain_order(order);
+            loaded.load_frame(&frame);
+            for y in 0..Frame::<2, 2>::HEIGHT {
+                for x in 0..Frame::<2, 2>::WIDTH {
+                    drawn.set_pixel(x, y, frame.get(x, y));
+                }
+            }
+            loaded.update().unwrap();
+            drawn.update().unwrap();
+            assert_eq!(loaded_spi.take(), drawn_spi.take());
+        }
+    }
+
+    #[test]
+    fn screen_stack_restores_in_reverse_order() {
+        let (mut display, _) = driver::<1, 1>();
+        let mut screens = ScreenStack::<2, 1, 1>::new();
+        let mut shown = Frame::new();
+
+        display.set_pixel(1, 1, true);
+        assert!(screens.push(&display));
+        display.clear();
+        display.set_pixel(2, 2, true);
+        assert!(screens.push(&display));
+        assert!(screens.is_full());
+        assert!(!screens.push(&display));
+        assert!(screens.top().is_some_and(|top| top.get(2, 2)));
+
+        display.clear();
+        assert!(screens.pop(&mut display));
+        display.store_frame(&mut shown);
+        assert!(shown.get(2, 2) && !shown.get(1, 1));
+        assert!(screens.pop(&mut display));
+        display.store_frame(&mut shown);
+        assert!(shown.get(1, 1) && !shown.get(2, 2));
+        assert!(!screens.pop(&mut display));
+        assert_eq!(screens.depth(), 0);
+    }
+}
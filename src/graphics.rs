@@ -0,0 +1,292 @@
+//! embedded-graphics support: the driver as a draw target and drawing adapters.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, OriginDimensions, Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Pixel,
+};
+use embedded_hal::digital::OutputPin;
+
+use crate::{BufferStorage, EnableLine, P10Led, PanelGeometry, Rotation, RowSelector};
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Color of the pixel at `point` in the framebuffer drawn into, `None` outside the
+    /// display. Pixels on panels that discard drawing read as off.
+    pub fn get_pixel(&self, point: Point) -> Option<BinaryColor> {
+        if !self.bounding_box().contains(point) {
+            return None;
+        }
+        let (x, y) = self.to_layout(point.x as usize, point.y as usize);
+        Some(self.pixel(x, y).into())
+    }
+
+    /// Whether drawing coordinates are layout coordinates.
+    fn untransformed(&self) -> bool {
+        self.rotation == Rotation::Deg0 && !self.mirror_x && !self.mirror_y
+    }
+
+    /// Set `len` pixels of display row `y` starting at `x`, a byte at a time.
+    fn fill_span(&mut self, x: usize, y: usize, len: usize, on: bool) {
+        if !self.untransformed() || self.pixel_remap.is_some() {
+            for x in x..x + len {
+                self.draw_pixel(x, y, on);
+            }
+            return;
+        }
+        self.content_changed = true;
+        let end = x + len;
+        let mut x = x;
+        while x < end {
+            // Panels are mapped as a whole, so a run within one panel stays contiguous, only
+            // reversed on a flipped panel.
+            let run_end = end.min((x / Self::PANEL_WIDTH + 1) * Self::PANEL_WIDTH);
+            if let Some((first, py)) = self.map_pixel(x, y) {
+                let last = self
+                    .map_pixel(run_end - 1, y)
+                    .map_or(first, |(last, _)| last);
+                self.fill_bits(first.min(last), py, run_end - x, on);
+            }
+            x = run_end;
+        }
+    }
+
+    /// Set `len` framebuffer pixels from `x`, `y` within one panel row.
+    fn fill_bits(&mut self, x: usize, y: usize, len: usize, on: bool) {
+        let start = self.pixel_to_bitmap_index(x, y);
+        let (first, last) = (x % 8, x % 8 + len);
+        let mut changed = false;
+        let bytes = &mut self.bitmap_mut()[start..start + last.div_ceil(8)];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let lo = first.saturating_sub(i * 8);
+            let hi = (last - i * 8).min(8);
+            let mask = ((0xffu16 >> lo) & !(0xffu16 >> hi)) as u8;
+            let old = *byte;
+            if on {
+                *byte &= !mask;
+            } else {
+                *byte |= mask;
+            }
+            changed |= *byte != old;
+        }
+        if changed && !self.double_buffered {
+            self.dirty |= 1 << (y % Self::PANEL_HEIGHT);
+        }
+    }
+}
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > DrawTarget for P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+        for Pixel(pos, color) in pixels
+            .into_iter()
+            .filter(|Pixel(pos, _color)| bb.contains(*pos))
+        {
+            self.draw_pixel(pos.x as _, pos.y as _, color.is_on());
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        if let Some(bottom_right) = area.bottom_right() {
+            for y in area.top_left.y..=bottom_right.y {
+                let (x, len) = (area.top_left.x as usize, area.size.width as usize);
+                self.fill_span(x, y as usize, len, color.is_on());
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let drawn = area.intersection(&self.bounding_box());
+        if drawn.is_zero_sized() {
+            return Ok(());
+        }
+        let mut colors = colors.into_iter();
+        let width = area.size.width as usize;
+        let skip_left = (drawn.top_left.x - area.top_left.x) as usize;
+        let len = drawn.size.width as usize;
+        let skip_above = (drawn.top_left.y - area.top_left.y) as usize;
+        colors.by_ref().take(skip_above * width).for_each(drop);
+        for y in drawn.rows() {
+            colors.by_ref().take(skip_left).for_each(drop);
+            // Runs of equal color are packed into bytes by `fill_span`.
+            let mut run: Option<(usize, usize, bool)> = None;
+            for (i, color) in colors.by_ref().take(len).enumerate() {
+                let (x, on) = (drawn.top_left.x as usize + i, color.is_on());
+                match run {
+                    Some((start, count, run_on)) if run_on == on => {
+                        run = Some((start, count + 1, run_on))
+                    }
+                    _ => {
+                        if let Some((start, count, run_on)) = run {
+                            self.fill_span(start, y as usize, count, run_on);
+                        }
+                        run = Some((x, 1, on));
+                    }
+                }
+            }
+            if let Some((start, count, run_on)) = run {
+                self.fill_span(start, y as usize, count, run_on);
+            }
+            colors.by_ref().take(width - skip_left - len).for_each(drop);
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill_all(color.is_on());
+        Ok(())
+    }
+}
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > OriginDimensions for P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    fn size(&self) -> Size {
+        let (width, height) = self.display_dims();
+        Size::new(width as _, height as _)
+    }
+}
+
+/// Pixel drawn outside the display through a [`Strict`] target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds(pub Point);
+
+/// Draw target returning an error for pixels outside the display instead of clipping them.
+///
+/// Pixels are drawn up to the first one out of bounds. Useful in tests to catch layout math
+/// bugs in application code.
+pub struct Strict<'a, T>(&'a mut T);
+
+impl<'a, T: DrawTarget<Error = core::convert::Infallible>> Strict<'a, T> {
+    pub fn new(target: &'a mut T) -> Self {
+        Self(target)
+    }
+}
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Opt into strict bounds-checked drawing, see [`Strict`].
+    pub fn strict(&mut self) -> Strict<'_, Self> {
+        Strict(self)
+    }
+}
+
+impl<T: Dimensions> Dimensions for Strict<'_, T> {
+    fn bounding_box(&self) -> Rectangle {
+        self.0.bounding_box()
+    }
+}
+
+impl<T: DrawTarget<Error = core::convert::Infallible>> DrawTarget for Strict<'_, T> {
+    type Color = T::Color;
+    type Error = OutOfBounds;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.0.bounding_box();
+        let mut outside = None;
+        let Ok(()) = self
+            .0
+            .draw_iter(pixels.into_iter().take_while(|Pixel(pos, _color)| {
+                let inside = bb.contains(*pos);
+                if !inside {
+                    outside = Some(*pos);
+                }
+                inside
+            }));
+        match outside {
+            Some(pos) => Err(OutOfBounds(pos)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Draw target discarding pixels outside `area`, e.g. for scrolling text inside a zone.
+pub struct Clipped<'a, T> {
+    target: &'a mut T,
+    area: Rectangle,
+}
+
+impl<'a, T: DrawTarget> Clipped<'a, T> {
+    pub fn new(target: &'a mut T, area: Rectangle) -> Self {
+        Self { target, area }
+    }
+}
+
+impl<T: Dimensions> Dimensions for Clipped<'_, T> {
+    fn bounding_box(&self) -> Rectangle {
+        self.area.intersection(&self.target.bounding_box())
+    }
+}
+
+impl<T: DrawTarget> DrawTarget for Clipped<'_, T> {
+    type Color = T::Color;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let area = self.area;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .filter(|Pixel(pos, _color)| area.contains(*pos)),
+        )
+    }
+}
@@ -0,0 +1,299 @@
+//! Grayscale by binary code modulation and, with fewer levels, by temporal dithering.
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::{Gray2, Gray4, GrayColor},
+    Pixel,
+};
+use embedded_hal::{digital::OutputPin, spi::SpiBus};
+
+use crate::{
+    Blocking, BufferStorage, EnableLine, Error, Frame, P10Led, Panel32x16, PanelGeometry,
+    RowSelector,
+};
+
+/// Duty in refreshes out of 15 for each level with a gamma of 2, see [`GrayFrame::set_gamma`].
+/// Every level above 0 gets at least one refresh, so dim levels do not go dark.
+const GAMMA: [u8; 16] = {
+    let mut table = [0; 16];
+    let mut level = 1;
+    while level < 16 {
+        let duty = (level * level + 7) / 15;
+        table[level] = if duty == 0 { 1 } else { duty as u8 };
+        level += 1;
+    }
+    table
+};
+
+/// Off-screen image with 4 bits per pixel, shown by [`P10Led::update_gray`].
+///
+/// Each bit of the levels is kept as a 1 bit [`Frame`], so the planes can be loaded into the
+/// driver like any other frame.
+pub struct GrayFrame<const PX: usize = 1, const PY: usize = 1, G = Panel32x16> {
+    /// Bit planes, least significant first.
+    planes: [Frame<PX, PY, G>; 4],
+    gamma: bool,
+}
+
+impl<const PX: usize, const PY: usize, G> Clone for GrayFrame<PX, PY, G> {
+    fn clone(&self) -> Self {
+        Self {
+            planes: self.planes.clone(),
+            gamma: self.gamma,
+        }
+    }
+}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> Default for GrayFrame<PX, PY, G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> GrayFrame<PX, PY, G> {
+    pub const WIDTH: usize = Frame::<PX, PY, G>::WIDTH;
+    pub const HEIGHT: usize = Frame::<PX, PY, G>::HEIGHT;
+
+    /// Create an all-off frame.
+    pub const fn new() -> Self {
+        Self {
+            planes: [Frame::new(), Frame::new(), Frame::new(), Frame::new()],
+            gamma: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for plane in &mut self.planes {
+            plane.clear();
+        }
+    }
+
+    /// Level `0..=15` of the pixel at `x`, `y`, `0` outside the frame.
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.planes
+            .iter()
+            .enumerate()
+            .fold(0, |level, (bit, plane)| {
+                level | (plane.get(x, y) as u8) << bit
+            })
+    }
+
+    /// Set the pixel at `x`, `y` to `level`, of which the low 4 bits are used.
+    pub fn set(&mut self, x: usize, y: usize, level: u8) {
+        let level = level & 0x0f;
+        let level = if self.gamma {
+            GAMMA[level as usize]
+        } else {
+            level
+        };
+        for (bit, plane) in self.planes.iter_mut().enumerate() {
+            plane.set(x, y, level & (1 << bit) != 0);
+        }
+    }
+
+    /// Map the levels set from then on through a gamma curve, so they look evenly spaced
+    /// instead of crowding at the bright end. Off by default; reads return the stored,
+    /// corrected levels.
+    pub fn set_gamma(&mut self, on: bool) {
+        self.gamma = on;
+    }
+
+    /// Bit plane `bit` of the levels, `0` being the least significant.
+    pub fn plane(&self, bit: usize) -> &Frame<PX, PY, G> {
+        &self.planes[bit]
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> DrawTarget for GrayFrame<PX, PY, G> {
+    type Color = Gray4;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if pos.x >= 0 && pos.y >= 0 {
+                self.set(pos.x as _, pos.y as _, color.luma());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> OriginDimensions for GrayFrame<PX, PY, G> {
+    fn size(&self) -> Size {
+        Size::new(Self::WIDTH as _, Self::HEIGHT as _)
+    }
+}
+
+/// Number of variants of a [`DitherFrame`], one per refresh.
+const VARIANTS: usize = 3;
+
+/// Off-screen image with 4 levels per pixel, shown by [`P10Led::update_dithered`].
+///
+/// The frame keeps 3 variants, a pixel of level `n` being lit in `n` of them. The variants
+/// are refreshed in turn, so each refresh costs no more than a plain one. Pixels are lit in
+/// different variants depending on their position, spreading the flicker over the display.
+pub struct DitherFrame<const PX: usize = 1, const PY: usize = 1, G = Panel32x16> {
+    variants: [Frame<PX, PY, G>; VARIANTS],
+    next: usize,
+}
+
+impl<const PX: usize, const PY: usize, G> Clone for DitherFrame<PX, PY, G> {
+    fn clone(&self) -> Self {
+        Self {
+            variants: self.variants.clone(),
+            next: self.next,
+        }
+    }
+}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> Default for DitherFrame<PX, PY, G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const PX: usize, const PY: usize, G: PanelGeometry> DitherFrame<PX, PY, G> {
+    pub const WIDTH: usize = Frame::<PX, PY, G>::WIDTH;
+    pub const HEIGHT: usize = Frame::<PX, PY, G>::HEIGHT;
+
+    /// Create an all-off frame.
+    pub const fn new() -> Self {
+        Self {
+            variants: [Frame::new(), Frame::new(), Frame::new()],
+            next: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for variant in &mut self.variants {
+            variant.clear();
+        }
+    }
+
+    /// Level `0..=3` of the pixel at `x`, `y`, `0` outside the frame.
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.variants.iter().filter(|v| v.get(x, y)).count() as u8
+    }
+
+    /// Set the pixel at `x`, `y` to `level`, clamped to `3`.
+    pub fn set(&mut self, x: usize, y: usize, level: u8) {
+        let level = level.min(VARIANTS as u8) as usize;
+        for (n, variant) in self.variants.iter_mut().enumerate() {
+            variant.set(x, y, (n + x + y) % VARIANTS < level);
+        }
+    }
+
+    /// Fill the frame from the 16 levels of `frame`, rounded to the nearest of 4.
+    pub fn load_gray(&mut self, frame: &GrayFrame<PX, PY, G>) {
+        for y in 0..Self::HEIGHT {
+            for x in 0..Self::WIDTH {
+                self.set(x, y, (frame.get(x, y) + 2) / 5);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> DrawTarget for DitherFrame<PX, PY, G> {
+    type Color = Gray2;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if pos.x >= 0 && pos.y >= 0 {
+                self.set(pos.x as _, pos.y as _, color.luma());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> OriginDimensions
+    for DitherFrame<PX, PY, G>
+{
+    fn size(&self) -> Size {
+        Size::new(Self::WIDTH as _, Self::HEIGHT as _)
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>
+{
+    /// Show `frame` once in 16 levels: each bit plane is loaded into the framebuffer and
+    /// refreshed with [`update`](Self::update) as often as its bit weighs, 15 refreshes in
+    /// all. Call it continuously instead of `update`; the framebuffer is left holding the
+    /// most significant plane.
+    pub fn update_gray(&mut self, frame: &GrayFrame<PX, PY, G>) -> Result<(), Error> {
+        for (bit, plane) in frame.planes.iter().enumerate() {
+            self.load_frame(plane);
+            for _ in 0..1 << bit {
+                self.update()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the next variant of `frame` and refresh once with [`update`](Self::update).
+    /// Call it continuously instead of `update`.
+    pub fn update_dithered(&mut self, frame: &mut DitherFrame<PX, PY, G>) -> Result<(), Error> {
+        self.load_frame(&frame.variants[frame.next]);
+        frame.next = (frame.next + 1) % VARIANTS;
+        self.update()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamma_table_spans_the_levels() {
+        assert_eq!((GAMMA[0], GAMMA[15]), (0, 15));
+        assert!(GAMMA[1] > 0);
+        assert!(GAMMA.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(GAMMA[8], 4);
+    }
+
+    #[test]
+    fn gray_levels_are_split_into_bit_planes() {
+        let mut frame = GrayFrame::<1, 1>::new();
+        frame.set(2, 3, 0b1010);
+        assert_eq!(frame.get(2, 3), 0b1010);
+        let planes: [bool; 4] = core::array::from_fn(|bit| frame.plane(bit).get(2, 3));
+        assert_eq!(planes, [false, true, false, true]);
+
+        frame.set_gamma(true);
+        frame.set(2, 3, 8);
+        assert_eq!(frame.get(2, 3), GAMMA[8]);
+    }
+
+    #[test]
+    fn dithered_levels_light_that_many_variants() {
+        let mut frame = DitherFrame::<1, 1>::new();
+        for level in 0..=4 {
+            frame.set(5, 6, level);
+            let lit = frame.variants.iter().filter(|v| v.get(5, 6)).count();
+            assert_eq!(lit, level.min(3) as usize);
+            assert_eq!(frame.get(5, 6), level.min(3));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Built-in 8x8 pictograms for status displays.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget, geometry::Point, pixelcolor::BinaryColor, Drawable, Pixel,
+};
+
+/// Common pictogram, drawn as [`Icon::SIZE`] square bitmap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Icon {
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    ArrowDown,
+    Wifi,
+    BatteryEmpty,
+    BatteryHalf,
+    BatteryFull,
+    Warning,
+    Play,
+    Pause,
+    Sun,
+    Cloud,
+    Rain,
+    Snow,
+    Storm,
+}
+
+impl Icon {
+    /// Width and height in pixels.
+    pub const SIZE: u32 = 8;
+
+    /// Rows of the bitmap, leftmost pixel in bit 7.
+    pub const fn bitmap(self) -> &'static [u8; 8] {
+        match self {
+            Self::ArrowLeft => &ARROW_LEFT,
+            Self::ArrowRight => &ARROW_RIGHT,
+            Self::ArrowUp => &ARROW_UP,
+            Self::ArrowDown => &ARROW_DOWN,
+            Self::Wifi => &WIFI,
+            Self::BatteryEmpty => &BATTERY_EMPTY,
+            Self::BatteryHalf => &BATTERY_HALF,
+            Self::BatteryFull => &BATTERY_FULL,
+            Self::Warning => &WARNING,
+            Self::Play => &PLAY,
+            Self::Pause => &PAUSE,
+            Self::Sun => &SUN,
+            Self::Cloud => &CLOUD,
+            Self::Rain => &RAIN,
+            Self::Snow => &SNOW,
+            Self::Storm => &STORM,
+        }
+    }
+
+    /// Pictogram with its top-left corner at `position`.
+    pub const fn at(self, position: Point) -> Pictogram {
+        Pictogram {
+            icon: self,
+            position,
+            color: BinaryColor::On,
+        }
+    }
+}
+
+/// An [`Icon`] placed on the display. Only the lit pixels are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pictogram {
+    pub icon: Icon,
+    pub position: Point,
+    pub color: BinaryColor,
+}
+
+impl Drawable for Pictogram {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let bitmap = self.icon.bitmap();
+        target.draw_iter((0..8).flat_map(|y| {
+            (0..8)
+                .filter(move |&x| bitmap[y] & (0x80 >> x) != 0)
+                .map(move |x| Pixel(self.position + Point::new(x, y as i32), self.color))
+        }))
+    }
+}
+
+const ARROW_LEFT: [u8; 8] = [
+    0b00001000, 0b00011000, 0b01111111, 0b11111111, 0b01111111, 0b00011000, 0b00001000, 0b00000000,
+];
+
+const ARROW_RIGHT: [u8; 8] = [
+    0b00010000, 0b00011000, 0b11111110, 0b11111111, 0b11111110, 0b00011000, 0b00010000, 0b00000000,
+];
+
+const ARROW_UP: [u8; 8] = [
+    0b00011000, 0b00111100, 0b01111110, 0b11111111, 0b00011000, 0b00011000, 0b00011000, 0b00011000,
+];
+
+const ARROW_DOWN: [u8; 8] = [
+    0b00011000, 0b00011000, 0b00011000, 0b00011000, 0b11111111, 0b01111110, 0b00111100, 0b00011000,
+];
+
+const WIFI: [u8; 8] = [
+    0b00000000, 0b01111110, 0b10000001, 0b00111100, 0b01000010, 0b00011000, 0b00011000, 0b00000000,
+];
+
+const BATTERY_EMPTY: [u8; 8] = [
+    0b00000000, 0b11111110, 0b10000011, 0b10000011, 0b10000011, 0b11111110, 0b00000000, 0b00000000,
+];
+
+const BATTERY_HALF: [u8; 8] = [
+    0b00000000, 0b11111110, 0b10110011, 0b10110011, 0b10110011, 0b11111110, 0b00000000, 0b00000000,
+];
+
+const BATTERY_FULL: [u8; 8] = [
+    0b00000000, 0b11111110, 0b10111111, 0b10111111, 0b10111111, 0b11111110, 0b00000000, 0b00000000,
+];
+
+const WARNING: [u8; 8] = [
+    0b00011000, 0b00100100, 0b00100100, 0b01011010, 0b01011010, 0b10000001, 0b10011001, 0b11111111,
+];
+
+const PLAY: [u8; 8] = [
+    0b01000000, 0b01100000, 0b01110000, 0b01111000, 0b01110000, 0b01100000, 0b01000000, 0b00000000,
+];
+
+const PAUSE: [u8; 8] = [
+    0b00000000, 0b01100110, 0b01100110, 0b01100110, 0b01100110, 0b01100110, 0b01100110, 0b00000000,
+];
+
+const SUN: [u8; 8] = [
+    0b10011001, 0b01000010, 0b00111100, 0b10111101, 0b10111101, 0b00111100, 0b01000010, 0b10011001,
+];
+
+const CLOUD: [u8; 8] = [
+    0b00000000, 0b00011000, 0b00100100, 0b01100010, 0b10000001, 0b10000001, 0b01111110, 0b00000000,
+];
+
+const RAIN: [u8; 8] = [
+    0b00011000, 0b00100100, 0b01000010, 0b10000001, 0b01111110, 0b00000000, 0b01001010, 0b10010100,
+];
+
+const SNOW: [u8; 8] = [
+    0b00011000, 0b01011010, 0b00111100, 0b11011011, 0b11011011, 0b00111100, 0b01011010, 0b00011000,
+];
+
+const STORM: [u8; 8] = [
+    0b00011000, 0b00100100, 0b01000010, 0b10000001, 0b01111110, 0b00001000, 0b00011000, 0b00010000,
+];
@@ -1,312 +1,3141 @@
 #![no_std]
 
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
 use core::marker::PhantomData;
 
-use embedded_graphics_core::{
-    geometry::{Dimensions, Size},
-    Pixel,
-};
-use embedded_hal::{
-    digital::{OutputPin, PinState},
-    spi::SpiBus,
-};
+use embedded_hal::{
+    digital::{OutputPin, PinState},
+    pwm::SetDutyCycle,
+    spi::{Operation, SpiBus, SpiDevice},
+};
+
+mod config;
+mod diagnostics;
+pub mod dual;
+#[cfg(feature = "graphics")]
+pub mod effect;
+pub mod font;
+mod frame;
+#[cfg(feature = "graphics")]
+mod graphics;
+mod gray;
+#[cfg(feature = "graphics")]
+pub mod icon;
+#[cfg(feature = "graphics")]
+pub mod marquee;
+pub mod mirror;
+#[cfg(test)]
+mod mock;
+mod refresh;
+#[cfg(feature = "graphics")]
+pub mod rgb;
+#[cfg(feature = "rtic")]
+pub mod rtic;
+mod run;
+pub mod settings;
+#[cfg(feature = "critical-section")]
+mod shared;
+mod split;
+#[cfg(feature = "graphics")]
+pub mod sprite;
+#[cfg(feature = "io")]
+pub mod storage;
+#[cfg(feature = "graphics")]
+pub mod text;
+mod time;
+#[cfg(feature = "graphics")]
+pub mod widget;
+pub use config::Config;
+pub use diagnostics::BurnTest;
+pub use frame::{Frame, FrameChannel, Receiver, ScreenStack, Sender, Stats};
+#[cfg(feature = "graphics")]
+pub use graphics::{Clipped, OutOfBounds, Strict};
+pub use gray::{DitherFrame, GrayFrame};
+#[cfg(feature = "critical-section")]
+pub use shared::SharedP10;
+pub use split::{Canvas, Scanner};
+pub use time::{AdaptiveRefresh, CameraRefresh, Clock, Profile, SoftDimming};
+
+use refresh::ready;
+
+/// Driver error. Bus and pin failures keep the kind reported by the HAL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    Spi(embedded_hal::spi::ErrorKind),
+    Pwm(embedded_hal::pwm::ErrorKind),
+    Digital(embedded_hal::digital::ErrorKind),
+    Config(ConfigError),
+}
+
+impl Error {
+    fn spi(e: impl embedded_hal::spi::Error) -> Self {
+        Self::Spi(e.kind())
+    }
+
+    fn digital(e: impl embedded_hal::digital::Error) -> Self {
+        Self::Digital(e.kind())
+    }
+
+    fn pwm(e: impl embedded_hal::pwm::Error) -> Self {
+        Self::Pwm(e.kind())
+    }
+}
+
+/// Invalid driver configuration, reported by the setters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A row remap entry is outside the panel height, or the rows of the panel are not a
+    /// permutation.
+    InvalidRowMap,
+    /// The row group order is not a permutation of `0..4`.
+    InvalidGroupOrder,
+    /// A dead panel bit is set beyond the panels of the layout.
+    InvalidDeadPanels,
+    /// A flipped panel bit is set beyond the panels of the layout.
+    InvalidFlippedPanels,
+    /// The pixel remap maps a pixel of the layout outside the framebuffer.
+    InvalidPixelRemap,
+    /// The scan phase is not a scan row of the scan mode.
+    InvalidScanPhase,
+    /// The scan rows of the scan mode do not divide the panel height.
+    InvalidScanMode,
+    /// The scan mode needs more row address lines than the row selector drives.
+    MissingAddressLines,
+    /// The scan order is not a permutation of the scan rows.
+    InvalidScanOrder,
+    /// The runtime panel layout is empty or larger than `PX` x `PY`.
+    InvalidLayout,
+    /// The external buffer is smaller than the framebuffer and scan cache.
+    BufferTooSmall,
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
+
+/// Order in which the pixels of each byte are clocked into the panel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Leftmost pixel of each 8-pixel group is sent first (stock HUB12 modules).
+    #[default]
+    MsbFirst,
+    /// Leftmost pixel is sent last, for boards that expect LSB-first data.
+    LsbFirst,
+}
+
+/// Level of the enable (OE) line that lights the LEDs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnablePolarity {
+    /// Output is on while the enable pin is high (stock HUB12 modules).
+    #[default]
+    ActiveHigh,
+    /// Output is on while the enable pin is low, for boards buffering OE through an inverter.
+    ActiveLow,
+}
+
+/// Size of a single panel module, selecting the type of the driver.
+///
+/// Implement it on a unit struct for HUB12 modules of other tile sizes; the scan, chain and
+/// mapping options then work on the new size. The width must be a multiple of 8, the height
+/// a multiple of 4 up to 16 and the panel at most 512 pixels, the size of a stock module, or
+/// the driver fails to compile. The scan mode must divide the height, see
+/// [`P10Led::set_scan_mode`].
+pub trait PanelGeometry {
+    /// Width of a panel in pixels.
+    const WIDTH: usize;
+    /// Height of a panel in pixels.
+    const HEIGHT: usize;
+}
+
+/// Stock 32x16 P10 module.
+pub struct Panel32x16;
+
+impl PanelGeometry for Panel32x16 {
+    const WIDTH: usize = 32;
+    const HEIGHT: usize = 16;
+}
+
+/// 16x16 P10 tile, half the width of the stock module with the same scan.
+///
+/// Name it in the [`Frame`] type too, e.g. `Frame<PX, PY, Panel16x16>`, to render frames of
+/// the chain's size.
+pub struct Panel16x16;
+
+impl PanelGeometry for Panel16x16 {
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 16;
+}
+
+/// Multiplexing of the panel rows, printed on modules as the scan ratio.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScanMode {
+    /// 1/2 scan, e.g. high-brightness outdoor modules: 2 scan rows selected by pin A alone,
+    /// each shifting out every second panel row.
+    Half,
+    /// 1/4 scan: 4 scan rows selected by pins A and B, each shifting out every fourth
+    /// panel row (stock indoor modules).
+    #[default]
+    Quarter,
+    /// 1/8 scan: 8 scan rows selected by pins A to C, each shifting out two panel rows.
+    Eighth,
+    /// 1/16 scan: every panel row is its own scan row, selected by pins A to D.
+    Sixteenth,
+}
+
+impl ScanMode {
+    /// Number of scan rows making up one refresh.
+    pub const fn rows(self) -> u8 {
+        match self {
+            Self::Half => 2,
+            Self::Quarter => 4,
+            Self::Eighth => 8,
+            Self::Sixteenth => 16,
+        }
+    }
+
+    /// Number of row address lines selecting the scan rows.
+    pub const fn lines(self) -> u8 {
+        self.rows().trailing_zeros() as u8
+    }
+}
+
+/// Order in which the panels of the layout are numbered along the data chain.
+///
+/// The data shifted out first ends up in the last panel of the chain, so index `0` is the
+/// panel farthest from the controller and the highest index the one it is plugged into. The
+/// dead and flipped panel masks count these indices. Every order starts at the top-left
+/// panel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChainOrder {
+    /// Left to right along every panel row, then on to the next row down; the controller
+    /// feeds the bottom-right panel.
+    #[default]
+    RowMajor,
+    /// Left to right along the first panel row, right to left along the second and so on,
+    /// for chains wired back and forth. If the panels of the returning rows are mounted
+    /// upside down, mark them with [`P10Led::set_flipped_panels`] as well. With a single
+    /// row of panels this is the same as `RowMajor`.
+    Serpentine,
+    /// Top to bottom along every panel column, then on to the next column to the right,
+    /// for tower-style installations; the controller feeds the bottom-right panel.
+    ColumnMajor,
+}
+
+/// Custom mapping of the layout pixel `x`, `y` to a framebuffer byte index and bit number
+/// (`7` is the MSB), replacing the stock HUB12 mapping, see [`P10Led::set_pixel_remap`].
+///
+/// The framebuffer holds the panel rows one after another, each as many bytes as the whole
+/// chain shifts per row; a cleared bit lights the LED. The byte index must stay below the
+/// panel height times the bytes of a row for the layout in use.
+pub type PixelRemap = fn(x: usize, y: usize) -> (usize, u8);
+
+/// Rotation of the drawing coordinates relative to the panel layout, for signs mounted
+/// sideways or upside down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    /// A quarter turn clockwise; width and height of the display are swapped.
+    Deg90,
+    Deg180,
+    /// Three quarter turns clockwise; width and height of the display are swapped.
+    Deg270,
+}
+
+impl Rotation {
+    /// Rotation by `turns` clockwise quarter turns, taken modulo 4.
+    pub const fn from_quarter_turns(turns: u8) -> Self {
+        match turns % 4 {
+            0 => Self::Deg0,
+            1 => Self::Deg90,
+            2 => Self::Deg180,
+            _ => Self::Deg270,
+        }
+    }
+
+    /// Number of clockwise quarter turns, `0..4`.
+    pub const fn quarter_turns(self) -> u8 {
+        self as u8
+    }
+
+    /// Whether width and height are swapped.
+    pub const fn is_transposed(self) -> bool {
+        matches!(self, Self::Deg90 | Self::Deg270)
+    }
+}
+
+/// Per-panel brightness compensation, e.g. for voltage drop along long 5 V runs.
+///
+/// All panels of a chain share the enable line, so the levels are applied by blanking a
+/// panel's data in a share of the refreshes: a level of `255` shows the panel on every
+/// refresh, `128` on every second one. Dim the panels nearest to the supply to match the
+/// far end of the chain. The levels apply on top of [`P10Led::set_brightness`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compensation {
+    /// Every panel at full level.
+    #[default]
+    None,
+    /// Levels interpolated linearly from the first to the last panel of the framebuffer mapping.
+    Linear { first: u8, last: u8 },
+    /// Explicit level per panel index. Missing entries default to full level.
+    PerPanel(&'static [u8]),
+}
+
+/// Handling of drawing to panel positions marked dead with [`P10Led::set_dead_panels`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeadPanelMode {
+    /// Drawing to a dead panel is discarded, the rest of the layout is unchanged.
+    #[default]
+    Discard,
+    /// Panels are renumbered skipping the dead ones: content of mapping index `n` goes to the
+    /// `n`-th working panel and whatever falls beyond the last working panel is discarded.
+    /// Keeps a single-row sign contiguous with a failed module in the middle.
+    Remap,
+}
+
+/// Stock interleave of the 4 rows shifted out per scan pass: `[r12, r8, r4, r0]`.
+pub const DEFAULT_GROUP_ORDER: [u8; 4] = [3, 2, 1, 0];
+
+/// Row address lines of the panel, selecting the scan row lit after the next latch.
+///
+/// [`AddressPins`] drives the lines from GPIOs and [`ShiftRegisterRows`] through a 74HC595;
+/// implement it for other row hardware and plug it in with [`P10Led::with_row_selector`].
+/// Boards with a 74HC138 driven from GPIOs use [`AddressPins`] on its inputs.
+pub trait RowSelector {
+    type Error: embedded_hal::digital::Error;
+
+    /// Number of address lines driven, starting at A; scan modes needing more lines than
+    /// this are rejected by [`P10Led::set_scan_mode`].
+    const LINES: u8;
+
+    /// Select scan row `row`, below the [`ScanMode::rows`] of the driver.
+    fn select_row(&mut self, row: u8) -> Result<(), Self::Error>;
+}
+
+/// Row address lines A to D driven from GPIOs, bit `n` of the scan row on line `n`.
+///
+/// 1/2 and 1/4 scan panels leave C and D unconnected ([`NoPin`]), 1/8 scan panels need C and
+/// 1/16 scan panels D as well, see [`P10Led::with_address_lines`]. `LINES` is the number of
+/// connected lines, reported as [`RowSelector::LINES`].
+pub struct AddressPins<A, B, C = NoPin, D = NoPin, const LINES: u8 = 2> {
+    pub a: A,
+    pub b: B,
+    pub c: C,
+    pub d: D,
+}
+
+impl<A: OutputPin, B: OutputPin> AddressPins<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            c: NoPin,
+            d: NoPin,
+        }
+    }
+}
+
+impl<A: OutputPin, B: OutputPin, C: OutputPin, D: OutputPin, const LINES: u8> RowSelector
+    for AddressPins<A, B, C, D, LINES>
+{
+    type Error = embedded_hal::digital::ErrorKind;
+
+    const LINES: u8 = LINES;
+
+    fn select_row(&mut self, row: u8) -> Result<(), Self::Error> {
+        set_line(&mut self.a, row & 0b0001 != 0)?;
+        set_line(&mut self.b, row & 0b0010 != 0)?;
+        set_line(&mut self.c, row & 0b0100 != 0)?;
+        set_line(&mut self.d, row & 0b1000 != 0)
+    }
+}
+
+/// Row address lines driven through a 74HC595 shift register on the controller board, saving
+/// GPIOs: the scan row is shifted in MSB first on `data` with rising edges of `clock` and
+/// applied on a pulse of `latch`, so outputs Q0 to Q3 carry lines A to D.
+///
+/// Use it with [`P10Led::with_row_selector`].
+pub struct ShiftRegisterRows<DS, SH, ST> {
+    data: DS,
+    clock: SH,
+    latch: ST,
+}
+
+impl<DS: OutputPin, SH: OutputPin, ST: OutputPin> ShiftRegisterRows<DS, SH, ST> {
+    pub fn new(data: DS, clock: SH, latch: ST) -> Self {
+        Self { data, clock, latch }
+    }
+
+    pub fn release(self) -> (DS, SH, ST) {
+        (self.data, self.clock, self.latch)
+    }
+}
+
+impl<DS: OutputPin, SH: OutputPin, ST: OutputPin> RowSelector for ShiftRegisterRows<DS, SH, ST> {
+    type Error = embedded_hal::digital::ErrorKind;
+
+    const LINES: u8 = 4;
+
+    fn select_row(&mut self, row: u8) -> Result<(), Self::Error> {
+        for bit in (0..8).rev() {
+            set_line(&mut self.data, row & (1 << bit) != 0)?;
+            set_line(&mut self.clock, true)?;
+            set_line(&mut self.clock, false)?;
+        }
+        set_line(&mut self.latch, true)?;
+        set_line(&mut self.latch, false)
+    }
+}
+
+/// Drive `pin` to `on`, for selectors combining pins with different error types.
+fn set_line(pin: &mut impl OutputPin, on: bool) -> Result<(), embedded_hal::digital::ErrorKind> {
+    pin.set_state(PinState::from(on))
+        .map_err(|e| embedded_hal::digital::Error::kind(&e))
+}
+
+/// Placeholder for an unconnected output, e.g. the unused address lines of [`AddressPins`].
+pub struct NoPin;
+
+impl embedded_hal::digital::ErrorType for NoPin {
+    type Error = core::convert::Infallible;
+}
+
+impl OutputPin for NoPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Placeholder for a released SPI bus, see [`P10Led::with_bus`]. Writes are discarded.
+pub struct NoBus;
+
+impl embedded_hal::spi::ErrorType for NoBus {
+    type Error = core::convert::Infallible;
+}
+
+impl SpiBus for NoBus {
+    fn read(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn write(&mut self, _words: &[u8]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Placeholder latch pin for wirings where the latch is driven by hardware, see [`CsLatch`].
+pub struct NoLatch;
+
+impl embedded_hal::digital::ErrorType for NoLatch {
+    type Error = core::convert::Infallible;
+}
+
+impl OutputPin for NoLatch {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Adapter driving the panel latch (STB) from the chip select of a [`SpiDevice`].
+///
+/// Every write is performed as one transaction, so CS is released right after the last bit
+/// and its rising edge latches the shifted data. This saves a GPIO and ties the latch timing
+/// to the data. Wire CS to STB and use [`NoLatch`] as the latch pin.
+///
+/// The data is latched before the driver switches to the next row address, so it would
+/// briefly show on the previous row. [`P10Led::new_cs_latch`] therefore turns on
+/// [shift blanking](P10Led::set_shift_blanking), switching the output off while a row is
+/// shifted; keep it on.
+///
+/// The same adapter runs the driver on a bus shared with other devices, e.g. through
+/// `embedded-hal-bus`: with a separate latch pin, see [`P10Led::new_device`], the bus is only
+/// held for the duration of each write.
+pub struct CsLatch<D>(pub D);
+
+impl<D: SpiDevice> embedded_hal::spi::ErrorType for CsLatch<D> {
+    type Error = D::Error;
+}
+
+impl<D: SpiDevice> SpiBus for CsLatch<D> {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.transaction(&mut [Operation::Read(words)])
+    }
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.transaction(&mut [Operation::Write(words)])
+    }
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        self.0.transaction(&mut [Operation::Transfer(read, write)])
+    }
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.transaction(&mut [Operation::TransferInPlace(words)])
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Adapter stretching the latch (STB) pulse to a fixed width with a delay provider.
+///
+/// Without it the pulse lasts as long as the GPIO calls take, which is too short for the
+/// panel shift registers on fast MCUs and shows up as ghost rows. Raising the pin waits
+/// `pulse_ns` before returning, so the strobe width no longer depends on the core clock.
+pub struct TimedLatch<L, D> {
+    pin: L,
+    delay: D,
+    pulse_ns: u32,
+}
+
+impl<L: OutputPin, D: embedded_hal::delay::DelayNs> TimedLatch<L, D> {
+    pub fn new(pin: L, delay: D, pulse_ns: u32) -> Self {
+        Self {
+            pin,
+            delay,
+            pulse_ns,
+        }
+    }
+
+    pub fn set_pulse_ns(&mut self, pulse_ns: u32) {
+        self.pulse_ns = pulse_ns;
+    }
+
+    pub fn pulse_ns(&self) -> u32 {
+        self.pulse_ns
+    }
+
+    pub fn release(self) -> (L, D) {
+        (self.pin, self.delay)
+    }
+}
+
+impl<L: OutputPin, D> embedded_hal::digital::ErrorType for TimedLatch<L, D> {
+    type Error = L::Error;
+}
+
+impl<L: OutputPin, D: embedded_hal::delay::DelayNs> OutputPin for TimedLatch<L, D> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low()
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high()?;
+        self.delay.delay_ns(self.pulse_ns);
+        Ok(())
+    }
+}
+
+/// Adapter keeping the enable line off for a guard time before lighting a row.
+///
+/// With [`P10Led::set_shift_blanking`] the output is off while a row is shifted in; the
+/// guard extends that window past the latch and the row address change, so the drivers and
+/// address decoders settle before the LEDs light and no ghost of the previous row shows.
+/// Switching on waits `guard_ns` first. Use it as the enable pin of the driver with the
+/// default [`EnablePolarity::ActiveHigh`].
+pub struct GuardedEnable<E, D> {
+    pin: E,
+    delay: D,
+    guard_ns: u32,
+}
+
+impl<E: OutputPin, D: embedded_hal::delay::DelayNs> GuardedEnable<E, D> {
+    pub fn new(pin: E, delay: D, guard_ns: u32) -> Self {
+        Self {
+            pin,
+            delay,
+            guard_ns,
+        }
+    }
+
+    pub fn set_guard_ns(&mut self, guard_ns: u32) {
+        self.guard_ns = guard_ns;
+    }
+
+    pub fn guard_ns(&self) -> u32 {
+        self.guard_ns
+    }
+
+    pub fn release(self) -> (E, D) {
+        (self.pin, self.delay)
+    }
+}
+
+impl<E: OutputPin, D> embedded_hal::digital::ErrorType for GuardedEnable<E, D> {
+    type Error = E::Error;
+}
+
+impl<E: OutputPin, D: embedded_hal::delay::DelayNs> OutputPin for GuardedEnable<E, D> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low()
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.delay.delay_ns(self.guard_ns);
+        self.pin.set_high()
+    }
+}
+
+/// Output enable line of the panels, driven high and low around every scan row.
+///
+/// Implemented for every [`OutputPin`] and for [`PwmEnable`], which also dims the panels.
+pub trait EnableLine {
+    /// Whether the line dims the panels itself. The driver then leaves the brightness and the
+    /// brightest [`Compensation`] level to [`set_duty`](Self::set_duty) instead of skipping
+    /// refreshes.
+    const DIMMING: bool = false;
+
+    fn set_line(&mut self, high: bool) -> Result<(), Error>;
+
+    /// Light output while the line is high, out of `u16::MAX`, taking effect right away.
+    /// Only called if the line is [`DIMMING`](Self::DIMMING).
+    fn set_duty(&mut self, duty: u16) -> Result<(), Error> {
+        let _ = duty;
+        Ok(())
+    }
+}
+
+impl<P: OutputPin> EnableLine for P {
+    fn set_line(&mut self, high: bool) -> Result<(), Error> {
+        self.set_state(PinState::from(high)).map_err(Error::digital)
+    }
+}
+
+/// Enable line driven by a PWM channel, so the panel brightness follows its duty cycle.
+///
+/// Switching the output on applies the duty of [`P10Led::set_brightness`], scaled by the
+/// brightest level of the [`Compensation`], switching it off drops the duty to zero. Panels
+/// compensated below that level are dimmed further by skipping refreshes. Use it as the
+/// enable pin of the driver with the default [`EnablePolarity::ActiveHigh`]; for inverted OE
+/// wiring, invert the timer channel instead.
+pub struct PwmEnable<P> {
+    pwm: P,
+    duty: u16,
+    enabled: bool,
+}
+
+/// Desired brightness level, polled once per refresh, see [`P10Led::set_brightness_hook`].
+pub type BrightnessHook = fn() -> u8;
+
+/// Level of the time bucket containing `minute` of the day, for a [`BrightnessHook`] reading
+/// a real-time clock.
+///
+/// `buckets` holds `(first_minute, level)` pairs sorted by minute; a minute before the first
+/// bucket belongs to the last one, which runs past midnight. Full brightness without buckets.
+pub fn scheduled_level(buckets: &[(u16, u8)], minute: u16) -> u8 {
+    buckets
+        .iter()
+        .rev()
+        .find(|&&(start, _)| start <= minute)
+        .or(buckets.last())
+        .map_or(u8::MAX, |&(_, level)| level)
+}
+
+/// Registered [`BrightnessHook`] with its debounce state.
+#[derive(Clone, Copy, Debug)]
+struct Hook {
+    source: BrightnessHook,
+    debounce: u16,
+    fade_us: u32,
+    /// Level differing from the current one and the number of polls it has been seen for.
+    pending: Option<(u8, u16)>,
+}
+
+/// Brightness ramp in progress, see [`P10Led::fade_to`].
+#[derive(Clone, Copy, Debug)]
+struct Fade {
+    from: u8,
+    to: u8,
+    duration_us: u32,
+    /// Set by the first step, so the ramp starts with the refresh loop.
+    start_us: Option<u32>,
+}
+
+/// Duty out of `u16::MAX` for each brightness level with a gamma of 2, so equal steps of the
+/// level look like equal steps of brightness.
+const GAMMA: [u16; 256] = {
+    let mut table = [0; 256];
+    let mut level = 0;
+    while level < 256 {
+        let square = level as u32 * level as u32;
+        table[level] = ((square * u16::MAX as u32 + 255 * 255 / 2) / (255 * 255)) as u16;
+        level += 1;
+    }
+    table
+};
+
+impl<P: SetDutyCycle> PwmEnable<P> {
+    /// Wrap `pwm` at full brightness.
+    pub fn new(pwm: P) -> Self {
+        Self {
+            pwm,
+            duty: u16::MAX,
+            enabled: false,
+        }
+    }
+
+    pub fn into_inner(self) -> P {
+        self.pwm
+    }
+
+    fn apply(&mut self) -> Result<(), Error> {
+        let duty = if self.enabled { self.duty } else { 0 };
+        self.pwm
+            .set_duty_cycle_fraction(duty, u16::MAX)
+            .map_err(Error::pwm)
+    }
+}
+
+impl<P: SetDutyCycle> EnableLine for PwmEnable<P> {
+    const DIMMING: bool = true;
+
+    fn set_line(&mut self, high: bool) -> Result<(), Error> {
+        self.enabled = high;
+        self.apply()
+    }
+
+    fn set_duty(&mut self, duty: u16) -> Result<(), Error> {
+        self.duty = duty;
+        self.apply()
+    }
+}
+
+/// Adapter running the async driver on a bare async [`SpiBus`](embedded_hal_async::spi::SpiBus)
+/// like the blocking one, for panels that have the bus to themselves.
+///
+/// Transactions are executed on the bus directly and flushed at the end so that the latch
+/// only follows completed transfers. The driver issues no delay operations, they are ignored.
+#[cfg(feature = "async")]
+pub struct BusDevice<B>(pub B);
+
+#[cfg(feature = "async")]
+impl<B: embedded_hal_async::spi::SpiBus> embedded_hal::spi::ErrorType for BusDevice<B> {
+    type Error = B::Error;
+}
+
+#[cfg(feature = "async")]
+impl<B: embedded_hal_async::spi::SpiBus> embedded_hal_async::spi::SpiDevice for BusDevice<B> {
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        for op in operations {
+            match op {
+                Operation::Read(words) => self.0.read(words).await?,
+                Operation::Write(words) => self.0.write(words).await?,
+                Operation::Transfer(read, write) => self.0.transfer(read, write).await?,
+                Operation::TransferInPlace(words) => self.0.transfer_in_place(words).await?,
+                Operation::DelayNs(_) => {}
+            }
+        }
+        self.0.flush().await
+    }
+}
+
+/// Scan data of one panel with every LED off, for any scan mode.
+const BLANK: [u8; 32] = [0xff; 32];
+
+/// Framebuffer, `0` = LED on: for every panel row, the row bytes of each panel in chain order.
+/// Sized for stock 32x16 panels, smaller panels leave the end unused.
+type Bitmap<const PX: usize, const PY: usize> = [[[[u8; 4]; PX]; PY]; 16];
+/// Scan data of all scan rows, each taking an equal share of the framebuffer size.
+type ScanCache<const PX: usize, const PY: usize> = [[[[u8; 16]; PX]; PY]; 4];
+
+/// Framebuffer and scan cache of a driver. Only byte arrays, so any suitably sized byte
+/// buffer is one.
+///
+/// Drivers keep them inline by default; [`new_with_buffer`](P10Led::new_with_buffer) places
+/// them in a buffer of the application instead, see [`BufferStorage`].
+#[repr(C)]
+pub struct Buffers<const PX: usize, const PY: usize> {
+    /// Displayed framebuffer.
+    bitmap: Bitmap<PX, PY>,
+    cache: ScanCache<PX, PY>,
+    /// Framebuffer drawn into while double buffering, see [`P10Led::swap_buffers`].
+    back: Bitmap<PX, PY>,
+}
+
+impl<const PX: usize, const PY: usize> Buffers<PX, PY> {
+    /// Buffers with every LED off.
+    const fn new() -> Self {
+        Self {
+            bitmap: [[[[0xff; 4]; PX]; PY]; 16],
+            cache: [[[[0xff; 16]; PX]; PY]; 4],
+            back: [[[[0xff; 4]; PX]; PY]; 16],
+        }
+    }
+
+    /// Switch every LED off in the framebuffers and the scan cache.
+    fn clear(&mut self) {
+        for bitmap in [&mut self.bitmap, &mut self.back] {
+            bitmap.as_flattened_mut().as_flattened_mut().fill([0xff; 4]);
+        }
+        self.cache
+            .as_flattened_mut()
+            .as_flattened_mut()
+            .fill([0xff; 16]);
+    }
+
+    /// Buffers placed at the start of `buffer`, every LED off.
+    fn place(buffer: &'static mut [u8]) -> Result<&'static mut Self, Error> {
+        let Some(buffer) = buffer.get_mut(..core::mem::size_of::<Self>()) else {
+            return Err(ConfigError::BufferTooSmall.into());
+        };
+        buffer.fill(0xff);
+        // Safety: `Buffers` consists of byte arrays only, so it has alignment 1, no padding
+        // and every bit pattern is valid; the length was checked above.
+        Ok(unsafe { &mut *buffer.as_mut_ptr().cast::<Self>() })
+    }
+}
+
+impl<const PX: usize, const PY: usize> AsRef<Buffers<PX, PY>> for Buffers<PX, PY> {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<const PX: usize, const PY: usize> AsMut<Buffers<PX, PY>> for Buffers<PX, PY> {
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+/// Where the [`Buffers`] of a driver live: the buffers themselves, kept inside the driver, or
+/// a mutable reference to them elsewhere.
+pub trait BufferStorage<const PX: usize, const PY: usize>:
+    AsRef<Buffers<PX, PY>> + AsMut<Buffers<PX, PY>>
+{
+}
+
+impl<T: AsRef<Buffers<PX, PY>> + AsMut<Buffers<PX, PY>>, const PX: usize, const PY: usize>
+    BufferStorage<PX, PY> for T
+{
+}
+
+pub struct Blocking;
+#[cfg(feature = "async")]
+pub struct Async;
+
+pub struct P10Led<
+    SPI,
+    E: EnableLine,
+    R: RowSelector,
+    L: OutputPin,
+    const PX: usize = 1,
+    const PY: usize = 1,
+    MODE = Blocking,
+    G: PanelGeometry = Panel32x16,
+    BUF: BufferStorage<PX, PY> = Buffers<PX, PY>,
+> {
+    spi: SPI,
+    enable: E,
+    rows: R,
+    latch: L,
+    buffers: BUF,
+    dirty: u16,
+    scan_row: u8,
+    row_map: Option<[u8; 16]>,
+    bit_order: BitOrder,
+    group_order: [u8; 4],
+    compensation: Compensation,
+    frame: u8,
+    dead_panels: u32,
+    dead_panel_mode: DeadPanelMode,
+    stats: Stats,
+    min_flush_interval_us: u32,
+    last_flush_us: Option<u32>,
+    adaptive_refresh: Option<AdaptiveRefresh>,
+    content_changed: bool,
+    last_change_us: u32,
+    profile: Profile,
+    panels_x: usize,
+    panels_y: usize,
+    enable_polarity: EnablePolarity,
+    flush_rows: u8,
+    refresh_rate_hz: Option<u16>,
+    next_row_us: Option<u32>,
+    rate_window: Option<(u32, u32)>,
+    double_buffered: bool,
+    precompute: bool,
+    inverted: bool,
+    rotation: Rotation,
+    mirror_x: bool,
+    mirror_y: bool,
+    flipped_panels: u32,
+    chain_order: ChainOrder,
+    pixel_remap: Option<PixelRemap>,
+    scan_mode: ScanMode,
+    blanked: bool,
+    shift_blanking: bool,
+    scan_order: Option<[u8; 16]>,
+    reversed_bytes: bool,
+    brightness: u8,
+    gamma: bool,
+    fade: Option<Fade>,
+    hook: Option<Hook>,
+    _mode: PhantomData<(MODE, G)>,
+}
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    pub const PANEL_WIDTH: usize = G::WIDTH;
+    pub const PANEL_HEIGHT: usize = G::HEIGHT;
+    /// Width of the largest layout, see [`width`](Self::width) for the one in use.
+    pub const WIDTH: usize = PX * Self::PANEL_WIDTH;
+    /// Height of the largest layout, see [`height`](Self::height) for the one in use.
+    pub const HEIGHT: usize = PY * Self::PANEL_HEIGHT;
+    pub const HEIGHT_IN_PANELS: usize = PY;
+
+    pub const fn row_width_bytes() -> usize {
+        if Self::WIDTH % 8 == 0 {
+            Self::WIDTH / 8
+        } else {
+            Self::WIDTH / 8 + 1
+        }
+    }
+    pub const fn unified_width_bytes() -> usize {
+        Self::row_width_bytes() * Self::HEIGHT_IN_PANELS
+    }
+
+    /// Tear the driver down and return the SPI bus and pins, e.g. to share the bus with an SD
+    /// card during an update. Refreshes end with the output blanked, so the panels stay dark
+    /// while the pins are released after one.
+    pub fn release(self) -> (SPI, E, R, L) {
+        (self.spi, self.enable, self.rows, self.latch)
+    }
+
+    /// Driver with the bus and row selector replaced by the ones `f` makes of the current
+    /// ones, along with whatever else `f` returns.
+    #[allow(clippy::type_complexity)]
+    fn map_parts<S2, R2: RowSelector, M2, T>(
+        self,
+        f: impl FnOnce(SPI, R) -> (S2, R2, T),
+    ) -> (P10Led<S2, E, R2, L, PX, PY, M2, G, BUF>, T) {
+        let (spi, rows, rest) = f(self.spi, self.rows);
+        let driver = P10Led {
+            spi,
+            enable: self.enable,
+            rows,
+            latch: self.latch,
+            buffers: self.buffers,
+            dirty: self.dirty,
+            scan_row: self.scan_row,
+            row_map: self.row_map,
+            bit_order: self.bit_order,
+            group_order: self.group_order,
+            compensation: self.compensation,
+            frame: self.frame,
+            dead_panels: self.dead_panels,
+            dead_panel_mode: self.dead_panel_mode,
+            stats: self.stats,
+            min_flush_interval_us: self.min_flush_interval_us,
+            last_flush_us: self.last_flush_us,
+            adaptive_refresh: self.adaptive_refresh,
+            content_changed: self.content_changed,
+            last_change_us: self.last_change_us,
+            profile: self.profile,
+            panels_x: self.panels_x,
+            panels_y: self.panels_y,
+            enable_polarity: self.enable_polarity,
+            flush_rows: self.flush_rows,
+            refresh_rate_hz: self.refresh_rate_hz,
+            next_row_us: self.next_row_us,
+            rate_window: self.rate_window,
+            double_buffered: self.double_buffered,
+            precompute: self.precompute,
+            inverted: self.inverted,
+            rotation: self.rotation,
+            mirror_x: self.mirror_x,
+            mirror_y: self.mirror_y,
+            flipped_panels: self.flipped_panels,
+            chain_order: self.chain_order,
+            pixel_remap: self.pixel_remap,
+            scan_mode: self.scan_mode,
+            blanked: self.blanked,
+            shift_blanking: self.shift_blanking,
+            scan_order: self.scan_order,
+            reversed_bytes: self.reversed_bytes,
+            brightness: self.brightness,
+            gamma: self.gamma,
+            fade: self.fade,
+            hook: self.hook,
+            _mode: PhantomData,
+        };
+        (driver, rest)
+    }
+
+    /// Select the rows through `selector` instead, e.g. a decoder or shift register on the
+    /// controller board. Returns the driver and the previous selector.
+    ///
+    /// The constructors take the two row address pins of stock panels; with other hardware
+    /// pass [`NoPin`] for both and swap in the actual selector here. The scan mode is kept, so
+    /// set a deeper one afterwards.
+    #[allow(clippy::type_complexity)]
+    pub fn with_row_selector<R2: RowSelector>(
+        self,
+        selector: R2,
+    ) -> (P10Led<SPI, E, R2, L, PX, PY, MODE, G, BUF>, R) {
+        self.map_parts(|spi, rows| (spi, selector, rows))
+    }
+
+    /// Run the driver on `spi` instead, returning the driver and the previous bus.
+    ///
+    /// Swapping in [`NoBus`] after [`sleep`](P10Led::sleep) frees the bus, e.g. to power
+    /// it down; swap the bus back in before [`wake`](P10Led::wake).
+    #[allow(clippy::type_complexity)]
+    pub fn with_bus<S2>(self, spi: S2) -> (P10Led<S2, E, R, L, PX, PY, MODE, G, BUF>, SPI) {
+        self.map_parts(|old, rows| (spi, rows, old))
+    }
+
+    /// Driver using `panels_x` by `panels_y` of the panels the buffers are sized for.
+    fn with_layout(mut self, panels_x: usize, panels_y: usize) -> Result<Self, Error> {
+        self.set_layout(panels_x, panels_y, self.chain_order)?;
+        Ok(self)
+    }
+
+    /// Switch to `panels_x` by `panels_y` panels chained in `order` with every LED off, the
+    /// state change of [`reconfigure`](P10Led::reconfigure).
+    fn set_layout(
+        &mut self,
+        panels_x: usize,
+        panels_y: usize,
+        order: ChainOrder,
+    ) -> Result<(), Error> {
+        if !(1..=PX).contains(&panels_x) || !(1..=PY).contains(&panels_y) {
+            return Err(ConfigError::InvalidLayout.into());
+        }
+        let panels = panels_x * panels_y;
+        if panels < 32 && self.dead_panels >> panels != 0 {
+            return Err(ConfigError::InvalidDeadPanels.into());
+        }
+        if panels < 32 && self.flipped_panels >> panels != 0 {
+            return Err(ConfigError::InvalidFlippedPanels.into());
+        }
+        if let Some(remap) = self.pixel_remap {
+            Self::check_pixel_remap(remap, panels_x, panels_y)?;
+        }
+        self.panels_x = panels_x;
+        self.panels_y = panels_y;
+        self.chain_order = order;
+        self.apply_dimming()?;
+        self.buffers.as_mut().clear();
+        self.scan_row = 0;
+        self.next_row_us = None;
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    /// Driver with default settings, shared by the constructors of all modes.
+    fn from_parts(spi: SPI, enable: E, rows: R, latch: L, buffers: BUF) -> Self {
+        let () = Self::LAYOUT_CHECK;
+        Self {
+            spi,
+            enable,
+            rows,
+            latch,
+            buffers,
+            dirty: 0,
+            scan_row: 0,
+            row_map: None,
+            bit_order: BitOrder::MsbFirst,
+            group_order: DEFAULT_GROUP_ORDER,
+            compensation: Compensation::None,
+            frame: 0,
+            dead_panels: 0,
+            dead_panel_mode: DeadPanelMode::Discard,
+            stats: Stats::default(),
+            min_flush_interval_us: 0,
+            last_flush_us: None,
+            adaptive_refresh: None,
+            content_changed: true,
+            last_change_us: 0,
+            profile: Profile::MaxRefresh,
+            panels_x: PX,
+            panels_y: PY,
+            enable_polarity: EnablePolarity::ActiveHigh,
+            flush_rows: 0,
+            refresh_rate_hz: None,
+            next_row_us: None,
+            rate_window: None,
+            double_buffered: false,
+            precompute: false,
+            inverted: false,
+            rotation: Rotation::Deg0,
+            mirror_x: false,
+            mirror_y: false,
+            flipped_panels: 0,
+            chain_order: ChainOrder::RowMajor,
+            pixel_remap: None,
+            scan_mode: ScanMode::Quarter,
+            blanked: false,
+            shift_blanking: false,
+            scan_order: None,
+            reversed_bytes: false,
+            brightness: u8::MAX,
+            gamma: false,
+            fade: None,
+            hook: None,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Panels across and down in use.
+    pub fn layout(&self) -> (usize, usize) {
+        (self.panels_x, self.panels_y)
+    }
+
+    /// Width of the layout in use in pixels.
+    pub fn width(&self) -> usize {
+        self.panels_x * Self::PANEL_WIDTH
+    }
+
+    /// Height of the layout in use in pixels.
+    pub fn height(&self) -> usize {
+        self.panels_y * Self::PANEL_HEIGHT
+    }
+
+    fn panels(&self) -> usize {
+        self.panels_x * self.panels_y
+    }
+
+    /// Framebuffer bytes per panel row of the layout in use.
+    fn row_bytes(&self) -> usize {
+        self.panels() * Self::PANEL_WIDTH / 8
+    }
+
+    fn pixel_to_bitmap_index(&self, x: usize, y: usize) -> usize {
+        let panel = (x / Self::PANEL_WIDTH) + (self.panels_x * (y / Self::PANEL_HEIGHT));
+        let x = (x % Self::PANEL_WIDTH) + (panel * Self::PANEL_WIDTH);
+        let y = y % Self::PANEL_HEIGHT;
+        x / 8 + y * self.row_bytes()
+    }
+
+    const fn pixel_to_bitmask(x: usize) -> u8 {
+        1 << (7 - x % 8)
+    }
+
+    /// Set a 16-entry row remap table applied while filling the scan cache.
+    ///
+    /// `map[n]` is the framebuffer row that is shifted out for physical panel row `n`,
+    /// so a clone panel with scrambled rows can be fixed from a config table.
+    /// `None` restores the straight mapping. Entries must be below the panel height and the
+    /// entries for the rows of the panel a permutation of them, so every framebuffer row is
+    /// shown exactly once.
+    pub fn set_row_map(&mut self, map: Option<[u8; 16]>) -> Result<(), Error> {
+        if let Some(map) = &map {
+            let height = Self::PANEL_HEIGHT as u8;
+            if map.iter().any(|&row| row >= height)
+                || (0..height).any(|row| !map[..height as usize].contains(&row))
+            {
+                return Err(ConfigError::InvalidRowMap.into());
+            }
+        }
+        self.row_map = map;
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    pub fn row_map(&self) -> Option<[u8; 16]> {
+        self.row_map
+    }
+
+    /// Set the bit order used when shifting out data.
+    ///
+    /// Boards expecting LSB-first data show mirrored 8-pixel groups with the default order.
+    pub fn set_bit_order(&mut self, order: BitOrder) {
+        self.bit_order = order;
+        self.invalidate_cache();
+    }
+
+    pub fn bit_order(&self) -> BitOrder {
+        self.bit_order
+    }
+
+    /// Send the bytes of each panel row in reverse order.
+    ///
+    /// Boards wired with the byte columns the other way round show the 8-pixel blocks of each
+    /// panel in mirrored order; combine it with [`BitOrder::LsbFirst`] if the pixels within
+    /// the blocks are mirrored as well.
+    pub fn set_byte_reversal(&mut self, on: bool) {
+        self.reversed_bytes = on;
+        self.invalidate_cache();
+    }
+
+    pub fn byte_reversal(&self) -> bool {
+        self.reversed_bytes
+    }
+
+    /// Show lit pixels dark and dark pixels lit, e.g. to flash the whole sign for an alarm.
+    ///
+    /// Applied when the scan data is built, the framebuffer is left as it is, so switching
+    /// back restores the image with the next refresh.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        if inverted != self.inverted {
+            self.inverted = inverted;
+            self.invalidate_cache();
+        }
+    }
+
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Set the level of the enable line that lights the LEDs. The output is switched off
+    /// with the new polarity right away.
+    pub fn set_enable_polarity(&mut self, polarity: EnablePolarity) -> Result<(), Error> {
+        self.enable_polarity = polarity;
+        self.set_output(false)
+    }
+
+    pub fn enable_polarity(&self) -> EnablePolarity {
+        self.enable_polarity
+    }
+
+    /// Blank the display right away and keep it blank: the output stays disabled and the
+    /// refreshes stop sending data until [`display_on`](Self::display_on). The framebuffer
+    /// and drawing are not affected.
+    pub fn display_off(&mut self) -> Result<(), Error> {
+        self.blanked = true;
+        self.set_output(false)
+    }
+
+    /// Resume the output with the next refresh, showing the framebuffer as it is by then.
+    pub fn display_on(&mut self) {
+        self.blanked = false;
+    }
+
+    pub fn is_display_on(&self) -> bool {
+        !self.blanked
+    }
+
+    /// Resume after [`sleep`](P10Led::sleep), starting with the next refresh.
+    pub fn wake(&mut self) {
+        self.display_on();
+    }
+
+    /// Switch the output off before the data of each row is shifted, instead of shifting
+    /// while the previous row is still lit. Cures ghost rows on panels whose shift registers
+    /// show through, at the cost of brightness for the shifting time; add a guard time after
+    /// the latch with [`GuardedEnable`]. Off by default, except with [`CsLatch`] latching on
+    /// the chip select, which needs it.
+    pub fn set_shift_blanking(&mut self, on: bool) {
+        self.shift_blanking = on;
+    }
+
+    pub fn shift_blanking(&self) -> bool {
+        self.shift_blanking
+    }
+
+    /// Switch the LED output on or off, honouring the enable polarity. Stays off while the
+    /// display is switched off.
+    fn set_output(&mut self, on: bool) -> Result<(), Error> {
+        let on = on && !self.blanked;
+        let high = on == (self.enable_polarity == EnablePolarity::ActiveHigh);
+        self.enable.set_line(high)
+    }
+
+    /// Set the order in which the 4 interleaved rows of a 1/4 scan pass are sent for each byte
+    /// column. Other scan modes send the rows from the bottom up.
+    ///
+    /// Entry `n` selects the row shifted out at position `n`: `0` is the scan row itself,
+    /// `1`..`3` are the rows 4, 8 and 12 lines below it. The stock order is
+    /// [`DEFAULT_GROUP_ORDER`]; some panels need `[0, 1, 2, 3]` or pairs swapped.
+    /// The order must be a permutation of `0..4`.
+    pub fn set_row_group_order(&mut self, order: [u8; 4]) -> Result<(), Error> {
+        if (0..4).any(|g| !order.contains(&g)) {
+            return Err(ConfigError::InvalidGroupOrder.into());
+        }
+        self.group_order = order;
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    pub fn row_group_order(&self) -> [u8; 4] {
+        self.group_order
+    }
+
+    /// Set the per-panel brightness compensation applied by `update` and `rescan`.
+    ///
+    /// With a [`PwmEnable`] the duty carries the brightest level, the others are reached by
+    /// skipping refreshes relative to it; otherwise every level is reached by skipping. The
+    /// levels apply on top of the [brightness](Self::set_brightness).
+    pub fn set_compensation(&mut self, compensation: Compensation) -> Result<(), Error> {
+        self.compensation = compensation;
+        self.apply_dimming()
+    }
+
+    /// Light output of the brightness level out of `u16::MAX`.
+    fn brightness_duty(&self) -> u32 {
+        if self.gamma {
+            GAMMA[self.brightness as usize] as u32
+        } else {
+            self.brightness as u32 * u16::MAX as u32 / u8::MAX as u32
+        }
+    }
+
+    /// Hand the brightness and the brightest compensation level to a dimming enable line.
+    fn apply_dimming(&mut self) -> Result<(), Error> {
+        if !E::DIMMING {
+            return Ok(());
+        }
+        let duty = self.brightness_duty() * self.peak_level() as u32 / u8::MAX as u32;
+        self.enable.set_duty(duty as u16)
+    }
+
+    /// Brightest compensation level of the panels.
+    fn peak_level(&self) -> u8 {
+        (0..self.panels())
+            .map(|panel| self.panel_level(panel))
+            .max()
+            .unwrap_or(u8::MAX)
+    }
+
+    pub fn compensation(&self) -> Compensation {
+        self.compensation
+    }
+
+    fn panel_level(&self, panel: usize) -> u8 {
+        let panels = self.panels();
+        match self.compensation {
+            Compensation::None => u8::MAX,
+            Compensation::Linear { first, last } => {
+                if panels < 2 {
+                    return first;
+                }
+                let (first, last) = (first as usize, last as usize);
+                let level = if last >= first {
+                    first + (last - first) * panel / (panels - 1)
+                } else {
+                    first - (first - last) * panel / (panels - 1)
+                };
+                level as u8
+            }
+            Compensation::PerPanel(levels) => levels.get(panel).copied().unwrap_or(u8::MAX),
+        }
+    }
+
+    /// Whether `panel` gets its data in the current refresh, spreading `level` visible
+    /// refreshes evenly over every 256.
+    fn panel_visible(&self, panel: usize) -> bool {
+        if self.is_dead(panel) {
+            return false;
+        }
+        let level = self.panel_level(panel) as u32;
+        let level = if E::DIMMING {
+            // The duty carries the brightness and the peak level already.
+            level * u8::MAX as u32 / self.peak_level().max(1) as u32
+        } else {
+            level * self.brightness_duty() / u16::MAX as u32
+        } as u16;
+        let frame = self.frame as u16;
+        level == u8::MAX as u16 || ((frame + 1) * level) >> 8 != (frame * level) >> 8
+    }
+
+    /// Whether some panels skip refreshes, see [`panel_visible`](Self::panel_visible).
+    pub(crate) fn skips_refreshes(&self) -> bool {
+        self.dead_panels != 0
+            || self.compensation != Compensation::None
+            || !E::DIMMING && self.brightness != u8::MAX
+    }
+
+    /// Select how the panel rows are multiplexed. The scan restarts at row 0 and the
+    /// [scan order](Self::set_scan_order) is reset.
+    ///
+    /// The scan rows must divide the panel height, e.g. a panel 8 rows high has no 1/16 scan,
+    /// and the [row selector](RowSelector::LINES) must drive the address lines they need.
+    pub fn set_scan_mode(&mut self, mode: ScanMode) -> Result<(), Error> {
+        if Self::PANEL_HEIGHT % mode.rows() as usize != 0 {
+            return Err(ConfigError::InvalidScanMode.into());
+        }
+        if mode.lines() > R::LINES {
+            return Err(ConfigError::MissingAddressLines.into());
+        }
+        self.scan_mode = mode;
+        self.scan_row = 0;
+        self.scan_order = None;
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    pub fn scan_mode(&self) -> ScanMode {
+        self.scan_mode
+    }
+
+    /// Set the row address sent for each scan row, for clone panels decoding the address
+    /// lines in another order, e.g. starting `[2, 0, 3, 1]` on 1/4 scan.
+    ///
+    /// Entry `n` is the address that lights scan row `n`; the data shifted for the row is
+    /// unchanged. Only the first [`ScanMode::rows`] entries are used and they must be a
+    /// permutation of the scan rows. `None` restores the straight order. Set the
+    /// [scan mode](Self::set_scan_mode) first.
+    pub fn set_scan_order(&mut self, order: Option<[u8; 16]>) -> Result<(), Error> {
+        if let Some(order) = &order {
+            let order = &order[..self.scan_rows() as usize];
+            if (0..self.scan_rows()).any(|row| !order.contains(&row)) {
+                return Err(ConfigError::InvalidScanOrder.into());
+            }
+        }
+        self.scan_order = order;
+        Ok(())
+    }
+
+    pub fn scan_order(&self) -> Option<[u8; 16]> {
+        self.scan_order
+    }
+
+    /// Address of `scan_row` on the row select lines.
+    fn row_address(&self, scan_row: u8) -> u8 {
+        self.scan_order
+            .map_or(scan_row, |order| order[scan_row as usize])
+    }
+
+    /// Number of scan rows making up one refresh.
+    fn scan_rows(&self) -> u8 {
+        self.scan_mode.rows()
+    }
+
+    /// Bytes of scan data per panel and scan row.
+    fn panel_scan_bytes(&self) -> usize {
+        Self::PANEL_WIDTH / 8 * self.scan_groups()
+    }
+
+    /// Number of panel rows lit together with each scan row.
+    fn scan_groups(&self) -> usize {
+        Self::PANEL_HEIGHT / self.scan_rows() as usize
+    }
+
+    /// Range of the scan data of `scan_row` in the flattened scan cache.
+    fn scan_data_range(&self, scan_row: usize) -> core::ops::Range<usize> {
+        let start = scan_row * PX * PY * Self::PANEL_WIDTH / 8 * self.scan_groups();
+        start..start + self.panels() * self.panel_scan_bytes()
+    }
+
+    /// Scan data of `scan_row` for the panels of the layout in use.
+    fn scan_data(&self, scan_row: usize) -> &[u8] {
+        let range = self.scan_data_range(scan_row);
+        &self
+            .buffers
+            .as_ref()
+            .cache
+            .as_flattened()
+            .as_flattened()
+            .as_flattened()[range]
+    }
+
+    /// Set the minimum interval between flushes done through `update_limited`, `0` disables it.
+    pub fn set_min_flush_interval_us(&mut self, interval: u32) {
+        self.min_flush_interval_us = interval;
+    }
+
+    pub fn min_flush_interval_us(&self) -> u32 {
+        self.min_flush_interval_us
+    }
+
+    /// Whether the flush interval has elapsed, recording `clock` as the last flush if so.
+    fn flush_due(&mut self, clock: &impl Clock) -> bool {
+        let now = clock.now_us();
+        if let Some(last) = self.last_flush_us {
+            if now.wrapping_sub(last) < self.min_flush_interval_us {
+                return false;
+            }
+        }
+        self.last_flush_us = Some(now);
+        true
+    }
+
+    /// Enable lowering the refresh duty while the framebuffer does not change,
+    /// see [`AdaptiveRefresh`]. `None` disables it.
+    pub fn set_adaptive_refresh(&mut self, adaptive: Option<AdaptiveRefresh>) {
+        self.adaptive_refresh = adaptive;
+    }
+
+    pub fn adaptive_refresh(&self) -> Option<AdaptiveRefresh> {
+        self.adaptive_refresh
+    }
+
+    /// Idle gap to insert after a refresh at `clock`, if the content has been static long enough.
+    fn idle_gap_us(&mut self, clock: &impl Clock) -> Option<u32> {
+        let now = clock.now_us();
+        if core::mem::take(&mut self.content_changed) {
+            self.last_change_us = now;
+        }
+        let adaptive = self.adaptive_refresh?;
+        (now.wrapping_sub(self.last_change_us) >= adaptive.idle_after_us)
+            .then_some(adaptive.idle_gap_us)
+    }
+
+    /// Apply the row hold, flush interval and idle gaps of `profile`, used by
+    /// `update_profiled`. The flush interval and idle gaps can still be changed afterwards.
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.min_flush_interval_us = profile.min_flush_interval_us();
+        self.adaptive_refresh = profile.adaptive_refresh();
+    }
+
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    /// Pace `update_paced` and `tick_paced` to `hz` full refreshes per second, so brightness
+    /// and flicker do not depend on the SPI speed of the MCU. `None` disables pacing.
+    pub fn set_refresh_rate(&mut self, hz: Option<u16>) {
+        self.refresh_rate_hz = hz.filter(|&hz| hz != 0);
+        self.next_row_us = None;
+    }
+
+    pub fn refresh_rate(&self) -> Option<u16> {
+        self.refresh_rate_hz
+    }
+
+    /// Time between two scan rows at the paced refresh rate.
+    fn row_period_us(&self) -> Option<u32> {
+        self.refresh_rate_hz
+            .map(|hz| 1_000_000 / (hz as u32 * self.scan_rows() as u32))
+    }
+
+    /// Deadline of the paced row following the one just sent, restarting the schedule
+    /// from now once it has fallen behind by more than a period.
+    fn next_row_deadline(&mut self, clock: &impl Clock, period: u32) -> Option<u32> {
+        let start = self.next_row_us.unwrap_or_else(|| clock.now_us());
+        let deadline = start.wrapping_add(period);
+        let remaining = time::remaining_us(clock, deadline);
+        self.next_row_us = remaining.map(|_| deadline);
+        remaining
+    }
+
+    /// Size of the buffer taken by [`new_with_buffer`](P10Led::new_with_buffer).
+    pub const BUFFER_BYTES: usize = core::mem::size_of::<Buffers<PX, PY>>();
+
+    /// Evaluated by the constructor, so an unusable layout fails to compile.
+    const LAYOUT_CHECK: () = {
+        assert!(
+            PX != 0 && PY != 0,
+            "P10Led needs at least one panel in each direction"
+        );
+        assert!(
+            Self::WIDTH <= u16::MAX as usize && Self::HEIGHT <= u16::MAX as usize,
+            "P10Led panel chain too large for its coordinate range"
+        );
+        assert!(
+            G::WIDTH != 0 && G::WIDTH % 8 == 0,
+            "PanelGeometry width must be a nonzero multiple of 8"
+        );
+        assert!(
+            G::HEIGHT != 0 && G::HEIGHT % 4 == 0 && G::HEIGHT <= 16,
+            "PanelGeometry height must be 4, 8, 12 or 16"
+        );
+        assert!(
+            G::WIDTH * G::HEIGHT <= 512,
+            "PanelGeometry larger than the 512 pixels of a stock panel"
+        );
+    };
+
+    fn source_row(&self, row: usize) -> usize {
+        match &self.row_map {
+            Some(map) => (map[row] as usize) % Self::PANEL_HEIGHT,
+            None => row,
+        }
+    }
+
+    /// Mark panel positions as absent or dead, one bit per panel index along the data
+    /// chain, see [`set_chain_order`](Self::set_chain_order).
+    ///
+    /// Dead panels are sent blank data, so a sign with a failed module can keep running
+    /// sensibly until repair. `mode` selects what happens to drawing aimed at them.
+    pub fn set_dead_panels(&mut self, mask: u32, mode: DeadPanelMode) -> Result<(), Error> {
+        if self.panels() < 32 && mask >> self.panels() != 0 {
+            return Err(ConfigError::InvalidDeadPanels.into());
+        }
+        self.dead_panels = mask;
+        self.dead_panel_mode = mode;
+        Ok(())
+    }
+
+    pub fn dead_panels(&self) -> (u32, DeadPanelMode) {
+        (self.dead_panels, self.dead_panel_mode)
+    }
+
+    /// Mark panels mounted upside down, one bit per panel index along the data chain,
+    /// e.g. every second one to shorten the cables.
+    ///
+    /// Pixels on a flipped panel are rotated 180° within the panel. Applies to drawing done
+    /// afterwards, the framebuffer is not rewritten.
+    pub fn set_flipped_panels(&mut self, mask: u32) -> Result<(), Error> {
+        if self.panels() < 32 && mask >> self.panels() != 0 {
+            return Err(ConfigError::InvalidFlippedPanels.into());
+        }
+        self.flipped_panels = mask;
+        Ok(())
+    }
+
+    pub fn flipped_panels(&self) -> u32 {
+        self.flipped_panels
+    }
+
+    fn is_dead(&self, panel: usize) -> bool {
+        panel < 32 && self.dead_panels & (1 << panel) != 0
+    }
+
+    /// Panel actually receiving the content of mapping index `panel`, `None` if it is discarded.
+    fn physical_panel(&self, panel: usize) -> Option<usize> {
+        match self.dead_panel_mode {
+            DeadPanelMode::Discard => (!self.is_dead(panel)).then_some(panel),
+            DeadPanelMode::Remap => (0..self.panels()).filter(|&p| !self.is_dead(p)).nth(panel),
+        }
+    }
+
+    /// Set the order in which the data chain runs through the panels.
+    ///
+    /// Applies to drawing done afterwards, the framebuffer is not rewritten. Panel indices of
+    /// the dead and flipped panel masks count along the chain from its far end, see
+    /// [`ChainOrder`].
+    pub fn set_chain_order(&mut self, order: ChainOrder) {
+        self.chain_order = order;
+        self.invalidate_cache();
+    }
+
+    pub fn chain_order(&self) -> ChainOrder {
+        self.chain_order
+    }
+
+    /// Position in the data chain of the panel in column `col` and row `row` of the layout.
+    fn chain_index(&self, col: usize, row: usize) -> usize {
+        let px = self.panels_x;
+        match self.chain_order {
+            ChainOrder::RowMajor => col + px * row,
+            ChainOrder::Serpentine if row % 2 == 1 => px - 1 - col + px * row,
+            ChainOrder::Serpentine => col + px * row,
+            ChainOrder::ColumnMajor => row + self.panels_y * col,
+        }
+    }
+
+    /// Override the stock mapping of layout pixels to framebuffer bits with `remap`, for clone
+    /// boards or hand-wired layouts, `None` to restore it.
+    ///
+    /// The chain order, dead and flipped panels are not applied to remapped pixels; rotation
+    /// and mirroring still apply before it. Every pixel of the layout in use is mapped once
+    /// to check that it lands in the framebuffer, [`reconfigure`](Self::reconfigure) checks
+    /// it again for the new layout. Applies to drawing done afterwards, the framebuffer is
+    /// not rewritten.
+    pub fn set_pixel_remap(&mut self, remap: Option<PixelRemap>) -> Result<(), Error> {
+        if let Some(remap) = remap {
+            Self::check_pixel_remap(remap, self.panels_x, self.panels_y)?;
+        }
+        self.pixel_remap = remap;
+        Ok(())
+    }
+
+    fn check_pixel_remap(remap: PixelRemap, panels_x: usize, panels_y: usize) -> Result<(), Error> {
+        let len = panels_x * panels_y * Self::PANEL_WIDTH / 8 * Self::PANEL_HEIGHT;
+        for y in 0..panels_y * Self::PANEL_HEIGHT {
+            for x in 0..panels_x * Self::PANEL_WIDTH {
+                let (byte_idx, bit) = remap(x, y);
+                if byte_idx >= len || bit >= 8 {
+                    return Err(ConfigError::InvalidPixelRemap.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn pixel_remap(&self) -> Option<PixelRemap> {
+        self.pixel_remap
+    }
+
+    /// Framebuffer byte index and bit mask of the layout pixel `x`, `y`, `None` if it is
+    /// discarded.
+    fn pixel_location(&self, x: usize, y: usize) -> Option<(usize, u8)> {
+        if let Some(remap) = self.pixel_remap {
+            let (byte_idx, bit) = remap(x, y);
+            let len = self.row_bytes() * Self::PANEL_HEIGHT;
+            return (byte_idx < len && bit < 8).then_some((byte_idx, 1 << bit));
+        }
+        let (x, y) = self.map_pixel(x, y)?;
+        Some((self.pixel_to_bitmap_index(x, y), Self::pixel_to_bitmask(x)))
+    }
+
+    /// Whether every layout pixel maps to itself: no dead or flipped panels and the chain
+    /// running row by row.
+    fn straight_mapping(&self) -> bool {
+        self.dead_panels == 0
+            && self.flipped_panels == 0
+            && self.chain_order == ChainOrder::RowMajor
+    }
+
+    /// Position in the physical panel layout of the display pixel `x`, `y`,
+    /// `None` if it is discarded.
+    fn map_pixel(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        if self.straight_mapping() {
+            return Some((x, y));
+        }
+        let px = self.panels_x;
+        let panel = self.chain_index(x / Self::PANEL_WIDTH, y / Self::PANEL_HEIGHT);
+        let panel = self.physical_panel(panel)?;
+        let (mut x, mut y) = (x % Self::PANEL_WIDTH, y % Self::PANEL_HEIGHT);
+        if panel < 32 && self.flipped_panels & (1 << panel) != 0 {
+            x = Self::PANEL_WIDTH - 1 - x;
+            y = Self::PANEL_HEIGHT - 1 - y;
+        }
+        Some((
+            (panel % px) * Self::PANEL_WIDTH + x,
+            (panel / px) * Self::PANEL_HEIGHT + y,
+        ))
+    }
+
+    /// Set the rotation of the drawing coordinates. The size of the draw target follows it,
+    /// frames loaded with [`load_frame`](Self::load_frame) keep the layout orientation.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Mirror the drawing coordinates horizontally (`x`) and/or vertically (`y`), e.g. for
+    /// signs viewed through glass or by rear projection. Applied in the direction of the
+    /// drawing coordinates, so it combines with any rotation as seen by the viewer.
+    pub fn set_mirror(&mut self, x: bool, y: bool) {
+        self.mirror_x = x;
+        self.mirror_y = y;
+    }
+
+    /// Horizontal and vertical mirroring set with [`set_mirror`](Self::set_mirror).
+    pub fn mirror(&self) -> (bool, bool) {
+        (self.mirror_x, self.mirror_y)
+    }
+
+    /// Width and height of the drawing coordinates.
+    fn display_dims(&self) -> (usize, usize) {
+        if self.rotation.is_transposed() {
+            (self.height(), self.width())
+        } else {
+            (self.width(), self.height())
+        }
+    }
+
+    /// Layout position of the drawing coordinates `x`, `y`, which must be on the display.
+    fn to_layout(&self, x: usize, y: usize) -> (usize, usize) {
+        let (dw, dh) = self.display_dims();
+        let x = if self.mirror_x { dw - 1 - x } else { x };
+        let y = if self.mirror_y { dh - 1 - y } else { y };
+        let (w, h) = (self.width(), self.height());
+        match self.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (y, h - 1 - x),
+            Rotation::Deg180 => (w - 1 - x, h - 1 - y),
+            Rotation::Deg270 => (w - 1 - y, x),
+        }
+    }
+
+    /// Set the pixel at drawing coordinates `x`, `y`, which must be on the display.
+    fn draw_pixel(&mut self, x: usize, y: usize, on: bool) {
+        let (x, y) = self.to_layout(x, y);
+        self.put_pixel(x, y, on);
+    }
+
+    /// Switch the pixel at `x`, `y` on or off, ignored outside the display. Same as drawing
+    /// a single pixel, without the embedded-graphics types.
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        let (w, h) = self.display_dims();
+        if x < w && y < h {
+            self.draw_pixel(x, y, on);
+        }
+    }
+
+    /// Switch every pixel on or off.
+    pub fn fill(&mut self, on: bool) {
+        self.fill_all(on);
+    }
+
+    /// Switch every pixel off.
+    pub fn clear(&mut self) {
+        self.fill_all(false);
+    }
+
+    /// Write `text` in the built-in [`font::FONT_5X7`] with its top left corner at `x`, `y`,
+    /// for basic signage without embedded-graphics. Only the glyph pixels are lit, the text
+    /// is clipped at the display edges. Returns the `x` following the text.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str) -> usize {
+        let font = &font::FONT_5X7;
+        let mut x = x;
+        for c in text.chars() {
+            if let Some(glyph) = font.glyph(c) {
+                for (dx, &column) in glyph.iter().enumerate() {
+                    for dy in 0..font.height as usize {
+                        if column & (1 << dy) != 0 {
+                            self.set_pixel(x + dx, y + dy, true);
+                        }
+                    }
+                }
+            }
+            x += font.advance() as usize;
+        }
+        x
+    }
+
+    /// State of the display pixel `x`, `y` in the framebuffer.
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        let Some((byte_idx, bit)) = self.pixel_location(x, y) else {
+            return false;
+        };
+        self.bitmap()[byte_idx] & bit == 0
+    }
+
+    fn put_pixel(&mut self, x: usize, y: usize, on: bool) {
+        self.content_changed = true;
+        let Some((byte_idx, bit)) = self.pixel_location(x, y) else {
+            return;
+        };
+        let row_bytes = self.row_bytes();
+        let byte = &mut self.bitmap_mut()[byte_idx];
+        let old = *byte;
+        if on {
+            *byte &= !bit; // and with the inverse of the bit - so
+        } else {
+            *byte |= bit; // set bit (which turns it off)
+        }
+        // Redrawing unchanged content keeps the scan data of the row; the back buffer is
+        // not scanned, swapping invalidates the whole cache instead.
+        if *byte != old && !self.double_buffered {
+            self.dirty |= 1 << (byte_idx / row_bytes);
+        }
+    }
+
+    /// Set every pixel of the framebuffer drawn into.
+    fn fill_all(&mut self, on: bool) {
+        self.bitmap_mut().fill(if on { 0x00 } else { 0xff });
+        if self.double_buffered {
+            self.content_changed = true;
+        } else {
+            self.invalidate_cache();
+        }
+    }
+
+    /// Framebuffer drawn into, the back buffer while double buffering.
+    fn bitmap(&self) -> &[u8] {
+        let buffers = self.buffers.as_ref();
+        let bitmap = if self.double_buffered {
+            &buffers.back
+        } else {
+            &buffers.bitmap
+        };
+        bitmap.as_flattened().as_flattened().as_flattened()
+    }
+
+    fn bitmap_mut(&mut self) -> &mut [u8] {
+        let double_buffered = self.double_buffered;
+        let buffers = self.buffers.as_mut();
+        let bitmap = if double_buffered {
+            &mut buffers.back
+        } else {
+            &mut buffers.bitmap
+        };
+        bitmap
+            .as_flattened_mut()
+            .as_flattened_mut()
+            .as_flattened_mut()
+    }
+
+    /// Fill the displayed framebuffer, bypassing the back buffer.
+    fn fill_bitmap(&mut self, on: bool) {
+        let bitmap = &mut self.buffers.as_mut().bitmap;
+        bitmap
+            .as_flattened_mut()
+            .as_flattened_mut()
+            .as_flattened_mut()
+            .fill(if on { 0x00 } else { 0xff });
+        self.invalidate_cache();
+    }
+
+    /// Draw into a back buffer that is only shown by [`swap_buffers`](Self::swap_buffers),
+    /// so the scan never reads a half-drawn frame. Enabling it starts the back buffer from
+    /// the displayed content.
+    pub fn set_double_buffered(&mut self, enabled: bool) {
+        if enabled && !self.double_buffered {
+            let buffers = self.buffers.as_mut();
+            buffers.back = buffers.bitmap;
+        }
+        self.double_buffered = enabled;
+    }
+
+    pub fn is_double_buffered(&self) -> bool {
+        self.double_buffered
+    }
+
+    /// Show the back buffer. The back buffer then holds the frame shown before, so redraw
+    /// it completely before the next swap. Does nothing without double buffering.
+    pub fn swap_buffers(&mut self) {
+        if !self.double_buffered {
+            return;
+        }
+        let buffers = self.buffers.as_mut();
+        core::mem::swap(&mut buffers.bitmap, &mut buffers.back);
+        self.invalidate_cache();
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.content_changed = true;
+        self.dirty = u16::MAX;
+    }
+
+    /// Rebuild all scan rows in one go when a frame starts instead of one row per scan
+    /// step, so the remaining steps of the refresh only write the bus and toggle pins. The
+    /// rows are rebuilt when the scan reaches row 0.
+    pub fn set_precompute(&mut self, enabled: bool) {
+        self.precompute = enabled;
+    }
+
+    pub fn precompute(&self) -> bool {
+        self.precompute
+    }
+
+    /// Rebuild the scan data of all scan rows from the framebuffer where it changed.
+    pub fn rasterize(&mut self) {
+        for row in 0..self.scan_rows() as usize {
+            self.fill_cache_row(row);
+        }
+    }
+
+    /// Bring the scan data of the current row up to date, see
+    /// [`set_precompute`](Self::set_precompute).
+    fn fill_cache(&mut self) {
+        if !self.precompute {
+            self.fill_cache_row(self.scan_row as usize);
+        } else if self.scan_row == 0 {
+            self.rasterize();
+        }
+    }
+
+    /// Rebuild the cached data of `scan_row`, if any of its source rows changed.
+    fn fill_cache_row(&mut self, scan_row: usize) {
+        let rowsize = self.row_bytes();
+        let rows = self.scan_rows() as usize;
+        let groups = self.scan_groups();
+        // Framebuffer rows lit with this scan row, in sending order.
+        let mut sources = [0; 8];
+        match self.scan_mode {
+            // Panels lower than 16 rows keep the order of the groups they have.
+            ScanMode::Quarter => {
+                let order = self.group_order.iter().map(|&g| g as usize);
+                for (source, group) in sources.iter_mut().zip(order.filter(|&g| g < groups)) {
+                    *source = self.source_row(scan_row + group * rows);
+                }
+            }
+            _ => {
+                for (n, source) in sources[..groups].iter_mut().enumerate() {
+                    *source = self.source_row(scan_row + (groups - 1 - n) * rows);
+                }
+            }
+        }
+        let sources = &sources[..groups];
+        let mask = sources.iter().fold(0u16, |mask, &row| mask | (1 << row));
+        if self.dirty & mask == 0 {
+            return;
+        }
+        self.dirty &= !mask;
+        let range = self.scan_data_range(scan_row);
+        let panel_bytes = Self::PANEL_WIDTH / 8;
+        let reversed = self.reversed_bytes;
+        let Buffers { bitmap, cache, .. } = self.buffers.as_mut();
+        let bitmap = bitmap.as_flattened().as_flattened().as_flattened();
+        let cache = &mut cache
+            .as_flattened_mut()
+            .as_flattened_mut()
+            .as_flattened_mut()[range];
+        for (column, chunk) in cache.chunks_exact_mut(groups).enumerate() {
+            let column = if reversed {
+                column - column % panel_bytes + panel_bytes - 1 - column % panel_bytes
+            } else {
+                column
+            };
+            for (byte, &row) in chunk.iter_mut().zip(sources) {
+                *byte = bitmap[row * rowsize + column];
+            }
+        }
+        if self.bit_order == BitOrder::LsbFirst {
+            for b in cache.iter_mut() {
+                *b = b.reverse_bits();
+            }
+        }
+        if self.inverted {
+            for b in cache.iter_mut() {
+                *b = !*b;
+            }
+        }
+    }
+
+    /// Fill the scan cache for the current row and return it, for backends that clock it out
+    /// themselves (e.g. by DMA).
+    ///
+    /// Once the transfer has finished call [`on_transfer_complete`](Self::on_transfer_complete),
+    /// typically from the HAL transfer-complete callback, so the scan keeps its cadence
+    /// regardless of what the CPU is busy with.
+    pub fn prepare_row(&mut self) -> &[u8] {
+        self.fill_cache();
+        self.scan_data(self.scan_row as usize)
+    }
+
+    /// Start a split-phase refresh of all scan rows and return the data of the first one
+    /// for the SPI DMA engine of the HAL.
+    ///
+    /// The returned slice stays valid and unchanged until the next
+    /// [`poll_flush`](Self::poll_flush), so its address can be handed to a DMA transfer;
+    /// use [`new_with_buffer`](P10Led::new_with_buffer) to place it in a DMA-reachable
+    /// region. The CPU is free while the data streams out.
+    pub fn start_flush(&mut self) -> &[u8] {
+        self.flush_rows = self.scan_rows();
+        self.prepare_row()
+    }
+
+    /// Latch the row transferred since the last call and return the data of the next one,
+    /// or `None` once all rows of the flush started with [`start_flush`](Self::start_flush)
+    /// are out. Call it when the DMA transfer has completed.
+    ///
+    /// The last row stays lit until the first transfer of the next flush completes, so back
+    /// to back flushes scan with an even duty.
+    pub fn poll_flush(&mut self) -> Result<Option<&[u8]>, Error> {
+        if self.flush_rows == 0 {
+            return Ok(None);
+        }
+        self.next_row()?;
+        self.flush_rows -= 1;
+        if self.flush_rows == 0 {
+            self.count_refresh();
+            return Ok(None);
+        }
+        Ok(Some(self.prepare_row()))
+    }
+
+    /// Advance the refresh counters at the end of a full scan.
+    fn count_refresh(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+        self.stats.refreshes = self.stats.refreshes.wrapping_add(1);
+    }
+
+    /// Whether a flush started with [`start_flush`](Self::start_flush) is still running.
+    pub fn is_flushing(&self) -> bool {
+        self.flush_rows != 0
+    }
+
+    /// Scan row that the next refresh step sends, below [`ScanMode::rows`].
+    pub fn scan_phase(&self) -> u8 {
+        self.scan_row
+    }
+
+    /// Continue the scan at `row`, e.g. to phase-lock the refresh to mains zero-crossings,
+    /// another display or a camera shutter and so avoid beat flicker.
+    ///
+    /// The row is sent by the next refresh step; calling this in the middle of a refresh
+    /// skips or repeats rows of that frame.
+    pub fn set_scan_phase(&mut self, row: u8) -> Result<(), Error> {
+        if row >= self.scan_rows() {
+            return Err(ConfigError::InvalidScanPhase.into());
+        }
+        self.scan_row = row;
+        Ok(())
+    }
+
+    /// Latch the data sent after [`prepare_row`](Self::prepare_row) and select the next row.
+    pub fn on_transfer_complete(&mut self) -> Result<(), Error> {
+        self.next_row()
+    }
+
+    fn next_row(&mut self) -> Result<(), Error> {
+        // Disable output
+        self.set_output(false)?;
+        // Latch
+        self.latch.set_high().map_err(Error::digital)?; // Latch DMD shift register output
+
+        // Digital outputs A, B are a 2-bit selector output, set from the scan_row variable (loops over 0-3),
+        // that determines which set of interleaved rows we are outputting during this pass.
+        // BA 0 (00) = 1,5,9,13
+        // BA 1 (01) = 2,6,10,14
+        // BA 2 (10) = 3,7,11,15
+        // BA 3 (11) = 4,8,12,16
+        // A 1/2 scan panel only has A (0 = odd rows, 1 = even rows), 1/8 and 1/16 scan panels
+        // add C and D as the higher bits.
+        let address = self.row_address(self.scan_row);
+        self.rows.select_row(address).map_err(Error::digital)?;
+        self.scan_row = (self.scan_row + 1) % self.scan_rows();
+        self.latch.set_low().map_err(Error::digital)?; // (Deliberately left as digitalWrite to ensure decent latching time)
+
+        self.set_output(true)?;
+
+        Ok(())
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        A: OutputPin,
+        B: OutputPin,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > P10Led<SPI, E, AddressPins<A, B>, L, PX, PY, Blocking, G>
+{
+    /// Create a driver selecting the rows with `pin_a` and `pin_b`, for 1/2 and 1/4 scan
+    /// panels. See [`with_address_lines`](P10Led::with_address_lines) for C and D.
+    pub fn new(spi: SPI, enable: E, pin_a: A, pin_b: B, latch: L) -> Result<Self, Error> {
+        Ok(Self::from_parts(
+            spi,
+            enable,
+            AddressPins::new(pin_a, pin_b),
+            latch,
+            Buffers::new(),
+        ))
+    }
+
+    /// Create a driver for `panels_x` by `panels_y` panels chosen at runtime, e.g. read
+    /// from EEPROM at boot, so one firmware serves signs of different sizes.
+    ///
+    /// `PX` and `PY` give the largest layout the buffers are sized for.
+    pub fn new_with_layout(
+        spi: SPI,
+        enable: E,
+        pin_a: A,
+        pin_b: B,
+        latch: L,
+        panels_x: usize,
+        panels_y: usize,
+    ) -> Result<Self, Error> {
+        Self::new(spi, enable, pin_a, pin_b, latch)?.with_layout(panels_x, panels_y)
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        A: OutputPin,
+        B: OutputPin,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > P10Led<SPI, E, AddressPins<A, B>, L, PX, PY, Blocking, G, &'static mut Buffers<PX, PY>>
+{
+    /// Create a driver keeping its framebuffer and scan cache in `buffer`, e.g. a static in
+    /// a RAM region reachable by DMA, instead of inside the driver. The driver only holds a
+    /// reference to it.
+    ///
+    /// `buffer` needs at least [`BUFFER_BYTES`](Self::BUFFER_BYTES) bytes.
+    pub fn new_with_buffer(
+        buffer: &'static mut [u8],
+        spi: SPI,
+        enable: E,
+        pin_a: A,
+        pin_b: B,
+        latch: L,
+    ) -> Result<Self, Error> {
+        Ok(Self::from_parts(
+            spi,
+            enable,
+            AddressPins::new(pin_a, pin_b),
+            latch,
+            Buffers::place(buffer)?,
+        ))
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>
+{
+    #[cfg(feature = "async")]
+    pub fn asynch(self) -> P10Led<SPI, E, R, L, PX, PY, Async, G, BUF> {
+        self.map_parts(|spi, rows| (spi, rows, ())).0
+    }
+
+    /// Method to flush framebuffer to display. This method needs to be called everytime a new framebuffer is created,
+    /// otherwise the frame will not appear on the screen.
+    pub fn update(&mut self) -> Result<(), Error> {
+        ready(self.scan())
+    }
+
+    /// Put the panels in a low power state until [`wake`](Self::wake): blank data is latched
+    /// into the chain, the output is held off like with [`display_off`](Self::display_off),
+    /// the latch is left low and the row address at 0. The framebuffer is kept.
+    pub fn sleep(&mut self) -> Result<(), Error> {
+        ready(self.enter_sleep())
+    }
+
+    /// Switch to `panels_x` by `panels_y` panels chained in `order` at runtime, e.g. when
+    /// the sign size is set over a serial protocol, within the `PX` x `PY` panels the
+    /// buffers are sized for.
+    ///
+    /// The framebuffer is cleared, since its content belongs to the old mapping, and blank
+    /// data is latched into the chain. The dead and flipped panel masks are kept and must
+    /// fit the new layout. On error nothing is changed.
+    pub fn reconfigure(
+        &mut self,
+        panels_x: usize,
+        panels_y: usize,
+        order: ChainOrder,
+    ) -> Result<(), Error> {
+        self.set_layout(panels_x, panels_y, order)?;
+        ready(self.blank_output())
+    }
+
+    /// Like [`update`](Self::update), but skipped if the minimum flush interval set with
+    /// [`set_min_flush_interval_us`](Self::set_min_flush_interval_us) has not elapsed
+    /// since the last flush. Returns whether the display was flushed.
+    pub fn update_limited(&mut self, clock: &impl Clock) -> Result<bool, Error> {
+        ready(self.scan_limited(clock))
+    }
+
+    /// Like [`update`](Self::update), followed by the idle gap of the adaptive refresh mode
+    /// once the framebuffer has been static for long enough. With the display blanked during
+    /// the gap, this cuts average power of mostly static signs while the next draw snaps back
+    /// to full rate.
+    pub fn update_adaptive(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+    ) -> Result<(), Error> {
+        ready(self.scan_adaptive(clock, delay))
+    }
+
+    /// Refresh according to the [`Profile`] set with [`set_profile`](Self::set_profile).
+    ///
+    /// The scan data is only rebuilt from the framebuffer once the flush interval has
+    /// elapsed, otherwise the retained data is sent again.
+    pub fn update_profiled(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+    ) -> Result<(), Error> {
+        ready(self.scan_profiled(clock, delay))
+    }
+
+    /// Scan the framebuffer `camera.passes` times with a fixed short row hold and no gaps
+    /// in between, so the display does not show rolling bands when filmed.
+    ///
+    /// `on_row` is called with the scan row right after it has been enabled, e.g. to restart
+    /// an external PWM dimming the enable line in phase with the scan.
+    pub fn update_camera(
+        &mut self,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+        camera: CameraRefresh,
+        on_row: impl FnMut(u8),
+    ) -> Result<(), Error> {
+        ready(self.scan_camera(delay, camera, on_row))
+    }
+
+    /// Like [`update`](Self::update), but each row is only lit for `dimming.on_us` and then
+    /// blanked for `dimming.off_us`. Coarse brightness control for boards where the enable
+    /// line is a plain GPIO; with a timer channel use [`PwmEnable`] instead.
+    pub fn update_dimmed(
+        &mut self,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+        dimming: SoftDimming,
+    ) -> Result<(), Error> {
+        ready(self.scan_dimmed(delay, dimming))
+    }
 
-#[derive(Clone, Copy, Debug)]
-pub enum Error {
-    Spi,
-    Pwm,
-    Digital,
+    /// Like [`update`](Self::update), with the rows spread evenly over the refresh period
+    /// set with [`set_refresh_rate`](Self::set_refresh_rate). Without a rate it is the same
+    /// as `update`.
+    pub fn update_paced(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+    ) -> Result<(), Error> {
+        ready(self.scan_paced(clock, delay))
+    }
+
+    /// Like [`tick`](Self::tick), then wait out the rest of the row period set with
+    /// [`set_refresh_rate`](Self::set_refresh_rate), for refresh loops without a timer.
+    pub fn tick_paced(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+    ) -> Result<(), Error> {
+        ready(self.scan_row_paced(clock, delay))
+    }
+
+    /// Like [`update`](Self::update), after stepping the fade started with
+    /// [`fade_to`](Self::fade_to).
+    pub fn update_fading(&mut self, clock: &impl Clock) -> Result<(), Error> {
+        self.step_fade(clock)?;
+        self.update()
+    }
+
+    /// Like [`update`](Self::update), timing the scan with `clock` for [`Stats::scan_us`]
+    /// and [`Stats::max_scan_us`] and sampling the refresh rate.
+    pub fn update_timed(&mut self, clock: &impl Clock) -> Result<(), Error> {
+        ready(self.scan_timed(clock))
+    }
+
+    /// Output exactly one scan row: rebuild its scan data, shift it out, latch and select it.
+    ///
+    /// Call it from a periodic timer interrupt, one tick per scan row makes a refresh with
+    /// each row lit until the next tick, while the main loop only draws. Unlike
+    /// [`update`](Self::update) the output is not blanked in between.
+    pub fn tick(&mut self) -> Result<(), Error> {
+        ready(self.scan_row())
+    }
+
+    /// Re-transmit the retained scan data without rebuilding it from the framebuffer.
+    ///
+    /// Intended for refresh ISRs keeping a static image alive with minimal CPU time.
+    /// Drawing done since the last [`update`](Self::update) is not shown.
+    pub fn rescan(&mut self) -> Result<(), Error> {
+        ready(self.scan_retained())
+    }
 }
 
-pub struct Blocking;
-#[cfg(feature = "async")]
-pub struct Async;
+impl<
+        D: SpiDevice,
+        E: EnableLine,
+        A: OutputPin,
+        B: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > P10Led<CsLatch<D>, E, AddressPins<A, B>, NoLatch, PX, PY, Blocking, G>
+{
+    /// Create a driver whose latch is the hardware chip select of `spi`, see [`CsLatch`].
+    /// Shift blanking is on, so rows are not latched while the previous one is lit.
+    pub fn new_cs_latch(spi: D, enable: E, pin_a: A, pin_b: B) -> Result<Self, Error> {
+        let mut driver = Self::new(CsLatch(spi), enable, pin_a, pin_b, NoLatch)?;
+        driver.set_shift_blanking(true);
+        Ok(driver)
+    }
+}
 
-pub struct P10Led<
-    SPI,
-    E: OutputPin,
-    A: OutputPin,
-    B: OutputPin,
-    L: OutputPin,
-    const PX: usize = 1,
-    const PY: usize = 1,
-    MODE = Blocking,
-> {
-    spi: SPI,
-    enable: E,
-    pin_a: A,
-    pin_b: B,
-    latch: L,
-    bitmap: [u8; 256], // TODO: size ???
-    cache: [u8; 64],   // TODO: size ???
-    scan_row: u8,
-    _mode: PhantomData<MODE>,
+impl<
+        D: SpiDevice,
+        E: EnableLine,
+        A: OutputPin,
+        B: OutputPin,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > P10Led<CsLatch<D>, E, AddressPins<A, B>, L, PX, PY, Blocking, G>
+{
+    /// Create a blocking driver on a [`SpiDevice`], e.g. a bus shared with a flash chip.
+    ///
+    /// Each write runs in its own managed transaction, so other devices can use the bus
+    /// between rows. The panel is latched by `latch` as with [`new`](Self::new). HUB12 has no
+    /// chip select, so gate the panel clock with the CS of `spi`, otherwise transfers to the
+    /// other devices are shifted into the panels as well.
+    pub fn new_device(spi: D, enable: E, pin_a: A, pin_b: B, latch: L) -> Result<Self, Error> {
+        Self::new(CsLatch(spi), enable, pin_a, pin_b, latch)
+    }
 }
 
 impl<
         SPI,
-        E: OutputPin,
+        E: EnableLine,
         A: OutputPin,
         B: OutputPin,
         L: OutputPin,
         const PX: usize,
         const PY: usize,
         MODE,
-    > P10Led<SPI, E, A, B, L, PX, PY, MODE>
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, AddressPins<A, B>, L, PX, PY, MODE, G, BUF>
 {
-    pub const PANEL_WIDTH: usize = 32;
-    pub const PANEL_HEIGHT: usize = 16;
-    pub const WIDTH: usize = PX * Self::PANEL_WIDTH;
-    pub const HEIGHT: usize = PY * Self::PANEL_HEIGHT;
-    pub const HEIGHT_IN_PANELS: usize = PY;
+    /// Add the C row address line of 1/8 scan panels. Select the scan depth with
+    /// [`set_scan_mode`](P10Led::set_scan_mode).
+    #[allow(clippy::type_complexity)]
+    pub fn with_address_line_c<C: OutputPin>(
+        self,
+        pin_c: C,
+    ) -> P10Led<SPI, E, AddressPins<A, B, C, NoPin, 3>, L, PX, PY, MODE, G, BUF> {
+        self.with_lines(pin_c, NoPin)
+    }
 
-    pub const fn row_width_bytes() -> usize {
-        if Self::WIDTH % 8 == 0 {
-            Self::WIDTH / 8
-        } else {
-            Self::WIDTH / 8 + 1
-        }
+    /// Add the C and D row address lines of 1/16 scan panels, which also run 1/8 scan. Select
+    /// the scan depth with [`set_scan_mode`](P10Led::set_scan_mode).
+    #[allow(clippy::type_complexity)]
+    pub fn with_address_lines<C: OutputPin, D: OutputPin>(
+        self,
+        pin_c: C,
+        pin_d: D,
+    ) -> P10Led<SPI, E, AddressPins<A, B, C, D, 4>, L, PX, PY, MODE, G, BUF> {
+        self.with_lines(pin_c, pin_d)
     }
-    pub const fn unified_width_bytes() -> usize {
-        Self::row_width_bytes() * Self::HEIGHT_IN_PANELS
+
+    /// Driver with `pin_c` and `pin_d` added to the address pins.
+    #[allow(clippy::type_complexity)]
+    fn with_lines<C: OutputPin, D: OutputPin, const LINES: u8>(
+        self,
+        pin_c: C,
+        pin_d: D,
+    ) -> P10Led<SPI, E, AddressPins<A, B, C, D, LINES>, L, PX, PY, MODE, G, BUF> {
+        let rows = |spi, rows: AddressPins<A, B>| {
+            let AddressPins { a, b, .. } = rows;
+            (
+                spi,
+                AddressPins {
+                    a,
+                    b,
+                    c: pin_c,
+                    d: pin_d,
+                },
+                (),
+            )
+        };
+        self.map_parts(rows).0
     }
+}
 
-    const fn pixel_to_bitmap_index(x: usize, y: usize) -> usize {
-        let panel = (x / Self::PANEL_WIDTH)
-            + ((Self::WIDTH / Self::PANEL_WIDTH) * (y / Self::PANEL_HEIGHT));
-        let x = (x % Self::PANEL_WIDTH) + (panel * Self::PANEL_WIDTH);
-        let y = y % Self::PANEL_HEIGHT;
-        x / 8 + y * Self::unified_width_bytes()
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Set the brightness of the whole display, `0` is off and `255` full brightness.
+    ///
+    /// A [`PwmEnable`] applies it as its duty, immediately while a row is lit; otherwise
+    /// panels skip refreshes like with a [`Compensation`], which stays in effect on top of
+    /// it. Cancels a fade in progress.
+    pub fn set_brightness(&mut self, level: u8) -> Result<(), Error> {
+        self.fade = None;
+        self.brightness = level;
+        self.apply_dimming()
     }
 
-    const fn pixel_to_bitmask(x: usize) -> u8 {
-        1 << (7 - x % 8)
+    pub fn brightness(&self) -> u8 {
+        self.brightness
     }
 
-    fn fill_cache(&mut self) {
-        let rowsize = Self::unified_width_bytes();
-        let scan_row = self.scan_row as usize;
-        {
-            for (chunk, (((&r0, &r4), &r8), &r12)) in self.cache.chunks_exact_mut(4).zip(
-                self.bitmap
-                    .iter()
-                    .skip((scan_row + 0) * rowsize)
-                    .take(rowsize)
-                    .zip(
-                        self.bitmap
-                            .iter()
-                            .skip((scan_row + 4) * rowsize)
-                            .take(rowsize),
-                    )
-                    .zip(
-                        self.bitmap
-                            .iter()
-                            .skip((scan_row + 8) * rowsize)
-                            .take(rowsize),
-                    )
-                    .zip(
-                        self.bitmap
-                            .iter()
-                            .skip((scan_row + 12) * rowsize)
-                            .take(rowsize),
-                    ),
-            ) {
-                chunk.copy_from_slice(&[r12, r8, r4, r0]);
-            }
-        }
+    /// Map the brightness level through a gamma curve, off by default, so
+    /// `set_brightness(128)` looks like half brightness. Without it the light output follows
+    /// the level linearly.
+    pub fn set_gamma(&mut self, on: bool) -> Result<(), Error> {
+        self.gamma = on;
+        self.apply_dimming()
     }
 
-    fn next_row(&mut self) -> Result<(), Error> {
-        // Disable PWM
-        self.enable.set_low().map_err(|_| Error::Digital)?;
-        // Latch
-        self.latch.set_high().map_err(|_| Error::Digital)?; // Latch DMD shift register output
+    pub fn gamma(&self) -> bool {
+        self.gamma
+    }
 
-        // Digital outputs A, B are a 2-bit selector output, set from the scan_row variable (loops over 0-3),
-        // that determines which set of interleaved rows we are outputting during this pass.
-        // BA 0 (00) = 1,5,9,13
-        // BA 1 (01) = 2,6,10,14
-        // BA 2 (10) = 3,7,11,15
-        // BA 3 (11) = 4,8,12,16
-        self.pin_a
-            .set_state(PinState::from(self.scan_row & 0b01 != 0))
-            .map_err(|_| Error::Digital)?;
-        self.pin_b
-            .set_state(PinState::from(self.scan_row & 0b10 != 0))
-            .map_err(|_| Error::Digital)?;
-        self.scan_row = (self.scan_row + 1) % 4;
-        self.latch.set_low().map_err(|_| Error::Digital)?; // (Deliberately left as digitalWrite to ensure decent latching time)
+    /// Ramp the brightness from its current level to `level` over `duration_us`, stepped by
+    /// [`step_fade`](Self::step_fade) or [`update_fading`](P10Led::update_fading). Replaces
+    /// a fade in progress, setting the brightness directly cancels it.
+    pub fn fade_to(&mut self, level: u8, duration_us: u32) {
+        self.fade = Some(Fade {
+            from: self.brightness,
+            to: level,
+            duration_us,
+            start_us: None,
+        });
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
 
-        self.enable.set_high().map_err(|_| Error::Digital)?;
+    /// Poll `hook` for the brightness at each [`step_fade`](Self::step_fade), e.g. from a
+    /// day/night table with [`scheduled_level`] or a light sensor. A new level is only taken
+    /// once the hook returned it for `debounce` polls in a row and is faded to over
+    /// `fade_us`, so a reading hovering at a threshold does not flicker. `None` removes it.
+    pub fn set_brightness_hook(
+        &mut self,
+        hook: Option<BrightnessHook>,
+        debounce: u16,
+        fade_us: u32,
+    ) {
+        self.hook = hook.map(|source| Hook {
+            source,
+            debounce,
+            fade_us,
+            pending: None,
+        });
+    }
 
-        Ok(())
+    /// Level the brightness is at or fading to.
+    pub(crate) fn target_brightness(&self) -> u8 {
+        self.fade.map_or(self.brightness, |fade| fade.to)
+    }
+
+    /// Poll the brightness hook, starting a fade once a new level is stable.
+    fn poll_hook(&mut self) {
+        let target = self.target_brightness();
+        let Some(hook) = &mut self.hook else {
+            return;
+        };
+        let level = (hook.source)();
+        let seen = match hook.pending {
+            _ if level == target => {
+                hook.pending = None;
+                return;
+            }
+            Some((pending, seen)) if pending == level => seen.saturating_add(1),
+            _ => 1,
+        };
+        if seen < hook.debounce {
+            hook.pending = Some((level, seen));
+            return;
+        }
+        hook.pending = None;
+        let fade_us = hook.fade_us;
+        self.fade_to(level, fade_us);
+    }
+
+    /// Apply the brightness due at the current time of the fade in progress, call it once per
+    /// refresh from any refresh loop. Polls the [`BrightnessHook`] first, if one is set.
+    /// Returns whether a fade is still going on.
+    pub fn step_fade(&mut self, clock: &impl Clock) -> Result<bool, Error> {
+        self.poll_hook();
+        let Some(fade) = &mut self.fade else {
+            return Ok(false);
+        };
+        let now = clock.now_us();
+        let elapsed = now.wrapping_sub(*fade.start_us.get_or_insert(now));
+        let (level, done) = if elapsed >= fade.duration_us {
+            (fade.to, true)
+        } else {
+            let span = fade.to as i32 - fade.from as i32;
+            let step = span as i64 * elapsed as i64 / fade.duration_us as i64;
+            ((fade.from as i32 + step as i32) as u8, false)
+        };
+        let fade = self.fade.take();
+        self.set_brightness(level)?;
+        if !done {
+            self.fade = fade;
+        }
+        Ok(!done)
     }
 }
 
+#[cfg(feature = "async")]
 impl<
-        SPI: SpiBus,
-        E: OutputPin,
+        SPI: embedded_hal_async::spi::SpiDevice,
+        E: EnableLine,
         A: OutputPin,
         B: OutputPin,
         L: OutputPin,
         const PX: usize,
         const PY: usize,
-    > P10Led<SPI, E, A, B, L, PX, PY, Blocking>
+        G: PanelGeometry,
+    > P10Led<SPI, E, AddressPins<A, B>, L, PX, PY, Async, G>
 {
-    pub fn new(spi: SPI, enable: E, pin_a: A, pin_b: B, latch: L) -> Result<Self, Error> {
-        Ok(Self {
+    /// Create an async driver on a [`SpiDevice`](embedded_hal_async::spi::SpiDevice). For a
+    /// bare async bus without chip select, wrap it in [`BusDevice`].
+    pub fn new_async(spi: SPI, enable: E, pin_a: A, pin_b: B, latch: L) -> Result<Self, Error> {
+        Ok(Self::from_parts(
             spi,
             enable,
-            pin_a,
-            pin_b,
+            AddressPins::new(pin_a, pin_b),
             latch,
-            bitmap: [0xff; 256],
-            cache: [0xff; 64],
-            scan_row: 0,
-            _mode: PhantomData,
-        })
-    }
-
-    #[cfg(feature = "async")]
-    pub fn asynch(self) -> P10Led<SPI, E, A, B, L, PX, PY, Async> {
-        P10Led {
-            spi: self.spi,
-            enable: self.enable,
-            pin_a: self.pin_a,
-            pin_b: self.pin_b,
-            latch: self.latch,
-            bitmap: self.bitmap,
-            cache: self.cache,
-            scan_row: self.scan_row,
-            _mode: PhantomData,
-        }
-    }
-
-    fn send_cache(&mut self) -> Result<(), Error> {
-        self.spi.write(&self.cache).map_err(|_| Error::Spi)
+            Buffers::new(),
+        ))
     }
 
-    /// Method to flush framebuffer to display. This method needs to be called everytime a new framebuffer is created,
-    /// otherwise the frame will not appear on the screen.
-    pub fn update(&mut self) -> Result<(), Error> {
-        for _ in 0..4 {
-            self.fill_cache();
-            self.send_cache()?;
-
-            self.next_row()?;
-        }
-        self.fill_cache();
-        self.send_cache()?;
-
-        self.enable.set_low().map_err(|_| Error::Digital)?;
-        for c in &mut self.cache {
-            *c = 0xff;
-        }
-        self.send_cache()?;
-        self.latch.set_high().map_err(|_| Error::Digital)?; // Latch DMD shift register output
-        self.latch.set_low().map_err(|_| Error::Digital)?; // (Deliberately left as digitalWrite to ensure decent latching time)
-        Ok(())
+    /// Create an async driver for `panels_x` by `panels_y` panels chosen at runtime, see
+    /// [`new_with_layout`](P10Led::new_with_layout).
+    pub fn new_async_with_layout(
+        spi: SPI,
+        enable: E,
+        pin_a: A,
+        pin_b: B,
+        latch: L,
+        panels_x: usize,
+        panels_y: usize,
+    ) -> Result<Self, Error> {
+        Self::new_async(spi, enable, pin_a, pin_b, latch)?.with_layout(panels_x, panels_y)
     }
 }
 
 #[cfg(feature = "async")]
 impl<
         SPI: embedded_hal_async::spi::SpiDevice,
-        E: OutputPin,
+        E: EnableLine,
         A: OutputPin,
         B: OutputPin,
         L: OutputPin,
         const PX: usize,
         const PY: usize,
-    > P10Led<SPI, E, A, B, L, PX, PY, Async>
+        G: PanelGeometry,
+    > P10Led<SPI, E, AddressPins<A, B>, L, PX, PY, Async, G, &'static mut Buffers<PX, PY>>
 {
-    pub fn blocking(self) -> P10Led<SPI, E, A, B, L, PX, PY, Blocking> {
-        P10Led {
-            spi: self.spi,
-            enable: self.enable,
-            pin_a: self.pin_a,
-            pin_b: self.pin_b,
-            latch: self.latch,
-            bitmap: self.bitmap,
-            cache: self.cache,
-            scan_row: self.scan_row,
-            _mode: PhantomData,
-        }
+    /// Create an async driver keeping its framebuffer and scan cache in `buffer`, see
+    /// [`new_with_buffer`](P10Led::new_with_buffer).
+    pub fn new_async_with_buffer(
+        buffer: &'static mut [u8],
+        spi: SPI,
+        enable: E,
+        pin_a: A,
+        pin_b: B,
+        latch: L,
+    ) -> Result<Self, Error> {
+        Ok(Self::from_parts(
+            spi,
+            enable,
+            AddressPins::new(pin_a, pin_b),
+            latch,
+            Buffers::place(buffer)?,
+        ))
     }
+}
 
-    async fn send_cache(&mut self) -> Result<(), Error> {
-        self.spi.write(&self.cache).await.map_err(|_| Error::Spi)
+#[cfg(feature = "async")]
+impl<
+        SPI: embedded_hal_async::spi::SpiDevice,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, Async, G, BUF>
+{
+    pub fn blocking(self) -> P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF> {
+        self.map_parts(|spi, rows| (spi, rows, ())).0
     }
 
     /// Method to flush framebuffer to display. This method needs to be called everytime a new framebuffer is created,
     /// otherwise the frame will not appear on the screen.
     pub async fn update(&mut self) -> Result<(), Error> {
-        for _ in 0..4 {
-            self.fill_cache();
-            self.send_cache().await?;
+        self.scan().await
+    }
+
+    /// Put the panels in a low power state until [`wake`](P10Led::wake), see the blocking
+    /// [`sleep`](P10Led::sleep).
+    pub async fn sleep(&mut self) -> Result<(), Error> {
+        self.enter_sleep().await
+    }
+
+    /// Switch the layout at runtime, see the blocking [`reconfigure`](P10Led::reconfigure).
+    pub async fn reconfigure(
+        &mut self,
+        panels_x: usize,
+        panels_y: usize,
+        order: ChainOrder,
+    ) -> Result<(), Error> {
+        self.set_layout(panels_x, panels_y, order)?;
+        self.blank_output().await
+    }
+
+    /// Like [`update`](Self::update), but skipped if the minimum flush interval set with
+    /// [`set_min_flush_interval_us`](Self::set_min_flush_interval_us) has not elapsed
+    /// since the last flush. Returns whether the display was flushed.
+    pub async fn update_limited(&mut self, clock: &impl Clock) -> Result<bool, Error> {
+        self.scan_limited(clock).await
+    }
+
+    /// Like [`update`](Self::update), followed by the idle gap of the adaptive refresh mode
+    /// once the framebuffer has been static for long enough.
+    pub async fn update_adaptive(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Error> {
+        self.scan_adaptive(clock, delay).await
+    }
+
+    /// Refresh according to the [`Profile`] set with [`set_profile`](P10Led::set_profile).
+    pub async fn update_profiled(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Error> {
+        self.scan_profiled(clock, delay).await
+    }
+
+    /// Scan the framebuffer with a fixed short row hold so the display does not show rolling
+    /// bands when filmed, see the blocking [`update_camera`](P10Led::update_camera).
+    pub async fn update_camera(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+        camera: CameraRefresh,
+        on_row: impl FnMut(u8),
+    ) -> Result<(), Error> {
+        self.scan_camera(delay, camera, on_row).await
+    }
+
+    /// Scan with each row only lit for `dimming.on_us`, see the blocking
+    /// [`update_dimmed`](P10Led::update_dimmed).
+    pub async fn update_dimmed(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+        dimming: SoftDimming,
+    ) -> Result<(), Error> {
+        self.scan_dimmed(delay, dimming).await
+    }
+
+    /// Refresh with the rows spread over the refresh period, see the blocking
+    /// [`update_paced`](P10Led::update_paced).
+    pub async fn update_paced(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Error> {
+        self.scan_paced(clock, delay).await
+    }
+
+    /// Output one scan row and wait out the rest of the row period, see the blocking
+    /// [`tick_paced`](P10Led::tick_paced).
+    pub async fn tick_paced(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Error> {
+        self.scan_row_paced(clock, delay).await
+    }
+
+    /// Refresh after stepping the fade, see the blocking
+    /// [`update_fading`](P10Led::update_fading).
+    pub async fn update_fading(&mut self, clock: &impl Clock) -> Result<(), Error> {
+        self.step_fade(clock)?;
+        self.update().await
+    }
+
+    /// Refresh, timing the scan with `clock`, see the blocking
+    /// [`update_timed`](P10Led::update_timed).
+    pub async fn update_timed(&mut self, clock: &impl Clock) -> Result<(), Error> {
+        self.scan_timed(clock).await
+    }
+
+    /// Output exactly one scan row, see the blocking [`tick`](P10Led::tick).
+    pub async fn tick(&mut self) -> Result<(), Error> {
+        self.scan_row().await
+    }
+
+    /// Re-transmit the retained scan data without rebuilding it from the framebuffer.
+    ///
+    /// Intended for refresh tasks keeping a static image alive with minimal CPU time.
+    /// Drawing done since the last [`update`](Self::update) is not shown.
+    pub async fn rescan(&mut self) -> Result<(), Error> {
+        self.scan_retained().await
+    }
+}
 
-            self.next_row()?;
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use std::{vec, vec::Vec};
+
+    use crate::{
+        mock::{driver, Driver, NoDelay, Pin, Spi},
+        BitOrder, ChainOrder, Config, ConfigError, DeadPanelMode, Error, P10Led, Rotation,
+        ScanMode, GAMMA,
+    };
+
+    /// Data shifted for each scan row by one refresh, in scan order.
+    fn refresh<const PX: usize, const PY: usize>(
+        display: &mut Driver<PX, PY>,
+        spi: &Spi,
+    ) -> Vec<Vec<u8>> {
+        display.update().unwrap();
+        let bytes = spi.take();
+        let rows = display.scan_rows() as usize;
+        let len = display.panels() * display.panel_scan_bytes();
+        // The rows, the first one again ending the refresh and the blank data.
+        assert_eq!(bytes.len(), (rows + 2) * len);
+        bytes.chunks(len).take(rows).map(<[u8]>::to_vec).collect()
+    }
+
+    /// Scan data of `rows` rows of `len` bytes, all off except `lit` bytes at scan row and
+    /// index.
+    fn expected(rows: usize, len: usize, lit: &[(usize, usize, u8)]) -> Vec<Vec<u8>> {
+        let mut data = vec![vec![0xff; len]; rows];
+        for &(row, index, byte) in lit {
+            data[row][index] = byte;
         }
-        self.fill_cache();
-        self.send_cache().await?;
+        data
+    }
 
-        self.enable.set_low().map_err(|_| Error::Digital)?;
-        for c in &mut self.cache {
-            *c = 0xff;
+    /// Refresh of a driver with the default options and the layout pixels `pixels` lit.
+    fn plain<const PX: usize, const PY: usize>(pixels: &[(usize, usize)]) -> Vec<Vec<u8>> {
+        let (mut display, spi) = driver::<PX, PY>();
+        for &(x, y) in pixels {
+            display.set_pixel(x, y, true);
         }
-        self.send_cache().await?;
-        self.latch.set_high().map_err(|_| Error::Digital)?; // Latch DMD shift register output
-        self.latch.set_low().map_err(|_| Error::Digital)?; // (Deliberately left as digitalWrite to ensure decent latching time)
-        Ok(())
+        refresh(&mut display, &spi)
     }
-}
 
-impl<
-        SPI,
-        E: OutputPin,
-        A: OutputPin,
-        B: OutputPin,
-        L: OutputPin,
-        const PX: usize,
-        const PY: usize,
-        MODE,
-    > embedded_graphics_core::draw_target::DrawTarget for P10Led<SPI, E, A, B, L, PX, PY, MODE>
-{
-    type Color = embedded_graphics_core::pixelcolor::BinaryColor;
-    type Error = core::convert::Infallible;
+    #[test]
+    fn quarter_scan_interleaves_four_rows_per_pass() {
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_pixel(0, 0, true);
+        display.set_pixel(9, 5, true);
+        display.set_pixel(31, 15, true);
+        // Each column byte is sent for rows 12, 8, 4 and 0 below the scan row, a cleared bit
+        // lighting the LED.
+        let want = expected(4, 16, &[(0, 3, 0x7f), (1, 6, 0xbf), (3, 12, 0xfe)]);
+        assert_eq!(refresh(&mut display, &spi), want);
+    }
+
+    #[test]
+    fn other_scan_modes_interleave_their_groups() {
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_scan_mode(ScanMode::Half).unwrap();
+        display.set_pixel(0, 0, true);
+        display.set_pixel(0, 3, true);
+        let want = expected(2, 32, &[(0, 7, 0x7f), (1, 6, 0x7f)]);
+        assert_eq!(refresh(&mut display, &spi), want);
+
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_scan_mode(ScanMode::Sixteenth).unwrap();
+        display.set_pixel(8, 5, true);
+        assert_eq!(
+            refresh(&mut display, &spi),
+            expected(16, 4, &[(5, 1, 0x7f)])
+        );
+    }
+
+    #[test]
+    fn scan_modes_need_their_address_lines() {
+        let missing = Err(Error::Config(ConfigError::MissingAddressLines));
+        let mut display: P10Led<_, _, _, _> =
+            P10Led::new(Spi::default(), Pin, Pin, Pin, Pin).unwrap();
+        assert_eq!(display.set_scan_mode(ScanMode::Eighth), missing);
+        assert_eq!(display.scan_mode(), ScanMode::Quarter);
+        let mut display = display.with_address_line_c(Pin);
+        assert_eq!(display.set_scan_mode(ScanMode::Eighth), Ok(()));
+        assert_eq!(display.set_scan_mode(ScanMode::Sixteenth), missing);
+
+        let config = Config::new().with_scan_mode(ScanMode::Eighth);
+        let display: Result<P10Led<_, _, _, _>, _> =
+            P10Led::new_with_config(Spi::default(), Pin, Pin, Pin, Pin, &config);
+        assert_eq!(
+            display.err(),
+            Some(Error::Config(ConfigError::MissingAddressLines))
+        );
+    }
+
+    #[test]
+    fn cs_latch_blanks_while_shifting() {
+        let display: P10Led<_, _, _, _> =
+            P10Led::new_cs_latch(Spi::default(), Pin, Pin, Pin).unwrap();
+        assert!(display.shift_blanking());
+    }
+
+    #[test]
+    fn row_map_picks_the_shifted_rows() {
+        let (mut display, spi) = driver::<1, 1>();
+        let mut reversed = [0; 16];
+        for (row, source) in reversed.iter_mut().enumerate() {
+            *source = 15 - row as u8;
+        }
+        display.set_row_map(Some(reversed)).unwrap();
+        display.set_pixel(0, 0, true);
+        assert_eq!(
+            refresh(&mut display, &spi),
+            expected(4, 16, &[(3, 0, 0x7f)])
+        );
+    }
+
+    #[test]
+    fn row_map_must_be_a_permutation() {
+        let (mut display, _) = driver::<1, 1>();
+        let mut map = [0; 16];
+        for (row, source) in map.iter_mut().enumerate() {
+            *source = row as u8;
+        }
+        map[1] = 0;
+        let invalid = Err(Error::Config(ConfigError::InvalidRowMap));
+        assert_eq!(display.set_row_map(Some(map)), invalid);
+        map[1] = 16;
+        assert_eq!(display.set_row_map(Some(map)), invalid);
+        assert_eq!(display.row_map(), None);
+    }
+
+    #[test]
+    fn group_order_sets_the_row_interleave() {
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_row_group_order([0, 1, 2, 3]).unwrap();
+        display.set_pixel(0, 0, true);
+        display.set_pixel(0, 12, true);
+        let want = expected(4, 16, &[(0, 0, 0x7f), (0, 3, 0x7f)]);
+        assert_eq!(refresh(&mut display, &spi), want);
+        assert_eq!(
+            display.set_row_group_order([0, 0, 1, 2]),
+            Err(Error::Config(ConfigError::InvalidGroupOrder))
+        );
+    }
+
+    #[test]
+    fn bit_order_and_byte_reversal() {
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_bit_order(BitOrder::LsbFirst);
+        display.set_pixel(0, 0, true);
+        assert_eq!(
+            refresh(&mut display, &spi),
+            expected(4, 16, &[(0, 3, 0xfe)])
+        );
 
-    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
-    where
-        I: IntoIterator<Item = Pixel<Self::Color>>,
-    {
-        let bb = self.bounding_box();
-        for Pixel(pos, color) in pixels
+        // The column bytes are reversed within each panel.
+        let (mut display, spi) = driver::<2, 1>();
+        display.set_byte_reversal(true);
+        display.set_pixel(0, 0, true);
+        display.set_pixel(32, 0, true);
+        let want = expected(4, 32, &[(0, 15, 0x7f), (0, 31, 0x7f)]);
+        assert_eq!(refresh(&mut display, &spi), want);
+    }
+
+    #[test]
+    fn inverted_output() {
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_inverted(true);
+        display.set_pixel(0, 0, true);
+        let want: Vec<Vec<u8>> = expected(4, 16, &[(0, 3, 0x7f)])
             .into_iter()
-            .filter(|Pixel(pos, _color)| bb.contains(*pos))
-        {
-            let byte_idx = Self::pixel_to_bitmap_index(pos.x as _, pos.y as _);
-            let bit = Self::pixel_to_bitmask(pos.x as _);
-            if color.is_on() {
-                self.bitmap[byte_idx] &= !bit; // and with the inverse of the bit - so
-            } else {
-                self.bitmap[byte_idx] |= bit; // set bit (which turns it off)
+            .map(|row| row.into_iter().map(|byte| !byte).collect())
+            .collect();
+        assert_eq!(refresh(&mut display, &spi), want);
+    }
+
+    #[test]
+    fn rotation_maps_drawing_onto_the_layout() {
+        let cases = [
+            (Rotation::Deg0, (1, 2)),
+            (Rotation::Deg90, (2, 14)),
+            (Rotation::Deg180, (30, 13)),
+            (Rotation::Deg270, (29, 1)),
+        ];
+        for (rotation, layout) in cases {
+            let (mut display, spi) = driver::<1, 1>();
+            display.set_rotation(rotation);
+            display.set_pixel(1, 2, true);
+            assert_eq!(
+                refresh(&mut display, &spi),
+                plain::<1, 1>(&[layout]),
+                "{rotation:?}"
+            );
+        }
+
+        // The display is 16 pixels wide on its side.
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_rotation(Rotation::Deg90);
+        display.set_pixel(16, 0, true);
+        assert_eq!(refresh(&mut display, &spi), plain::<1, 1>(&[]));
+    }
+
+    #[test]
+    fn mirroring_flips_the_drawing_axes() {
+        let cases = [
+            ((true, false), (30, 2)),
+            ((false, true), (1, 13)),
+            ((true, true), (30, 13)),
+        ];
+        for ((x, y), layout) in cases {
+            let (mut display, spi) = driver::<1, 1>();
+            display.set_mirror(x, y);
+            display.set_pixel(1, 2, true);
+            assert_eq!(refresh(&mut display, &spi), plain::<1, 1>(&[layout]));
+        }
+    }
+
+    #[test]
+    fn flipped_panels_turn_their_content() {
+        let (mut display, spi) = driver::<2, 1>();
+        display.set_flipped_panels(0b10).unwrap();
+        display.set_pixel(1, 1, true);
+        display.set_pixel(33, 1, true);
+        assert_eq!(
+            refresh(&mut display, &spi),
+            plain::<2, 1>(&[(1, 1), (62, 14)])
+        );
+        assert_eq!(
+            display.set_flipped_panels(0b100),
+            Err(Error::Config(ConfigError::InvalidFlippedPanels))
+        );
+    }
+
+    #[test]
+    fn dead_panels_get_blank_data() {
+        let (mut display, spi) = driver::<2, 1>();
+        for y in 0..16 {
+            for x in 0..64 {
+                display.set_pixel(x, y, true);
             }
         }
-        Ok(())
+        display
+            .set_dead_panels(0b01, DeadPanelMode::Discard)
+            .unwrap();
+        for row in refresh(&mut display, &spi) {
+            assert!(row[..16].iter().all(|&byte| byte == 0xff));
+            assert!(row[16..].iter().all(|&byte| byte == 0x00));
+        }
     }
-}
-impl<
-        SPI,
-        E: OutputPin,
-        A: OutputPin,
-        B: OutputPin,
-        L: OutputPin,
-        const PX: usize,
-        const PY: usize,
-        MODE,
-    > embedded_graphics_core::geometry::OriginDimensions for P10Led<SPI, E, A, B, L, PX, PY, MODE>
-{
-    fn size(&self) -> Size {
-        Size::new(Self::WIDTH as _, Self::HEIGHT as _)
+
+    #[test]
+    fn dead_panels_are_skipped_when_remapped() {
+        let (mut display, spi) = driver::<3, 1>();
+        display
+            .set_dead_panels(0b010, DeadPanelMode::Remap)
+            .unwrap();
+        display.set_pixel(1, 0, true);
+        display.set_pixel(33, 0, true);
+        // Beyond the last working panel.
+        display.set_pixel(65, 0, true);
+        assert_eq!(
+            refresh(&mut display, &spi),
+            plain::<3, 1>(&[(1, 0), (65, 0)])
+        );
+    }
+
+    /// Stock mapping of a 2 x 1 layout.
+    fn stock_remap(x: usize, y: usize) -> (usize, u8) {
+        (x / 8 + y * 8, 7 - (x % 8) as u8)
+    }
+
+    #[test]
+    fn pixel_remap_replaces_the_mapping() {
+        let (mut display, spi) = driver::<2, 1>();
+        display.set_pixel_remap(Some(stock_remap)).unwrap();
+        display.set_pixel(3, 7, true);
+        display.set_pixel(40, 15, true);
+        assert_eq!(
+            refresh(&mut display, &spi),
+            plain::<2, 1>(&[(3, 7), (40, 15)])
+        );
+
+        fn swap_halves(x: usize, y: usize) -> (usize, u8) {
+            stock_remap((x + 32) % 64, y)
+        }
+        display.set_pixel_remap(Some(swap_halves)).unwrap();
+        display.clear();
+        display.set_pixel(3, 7, true);
+        assert_eq!(refresh(&mut display, &spi), plain::<2, 1>(&[(35, 7)]));
+    }
+
+    #[test]
+    fn pixel_remap_must_stay_in_the_framebuffer() {
+        let invalid = Err(Error::Config(ConfigError::InvalidPixelRemap));
+        let (mut display, _) = driver::<2, 1>();
+        fn bit_out_of_range(x: usize, y: usize) -> (usize, u8) {
+            (stock_remap(x, y).0, 8)
+        }
+        fn rows_out_of_range(x: usize, y: usize) -> (usize, u8) {
+            stock_remap(x, y + 1)
+        }
+        assert_eq!(display.set_pixel_remap(Some(bit_out_of_range)), invalid);
+        assert_eq!(display.set_pixel_remap(Some(rows_out_of_range)), invalid);
+        assert!(display.pixel_remap().is_none());
+
+        // A remap for two panels does not fit a single one.
+        display.set_pixel_remap(Some(stock_remap)).unwrap();
+        assert_eq!(display.reconfigure(1, 1, ChainOrder::RowMajor), invalid);
+        assert_eq!(display.width(), 64);
+    }
+
+    #[test]
+    fn gamma_table_spans_the_duty() {
+        assert_eq!(GAMMA[0], 0);
+        assert_eq!(GAMMA[255], u16::MAX);
+        assert!(GAMMA.windows(2).all(|pair| pair[0] <= pair[1]));
+        // A gamma of 2: half the level is a quarter of the duty.
+        assert_eq!(GAMMA[128], 16513);
+    }
+
+    #[test]
+    fn fade_ramps_the_brightness() {
+        let (mut display, _) = driver::<1, 1>();
+        let now = Cell::new(1000);
+        let clock = || now.get();
+        display.fade_to(0, 1000);
+        assert_eq!(display.target_brightness(), 0);
+        // The ramp starts with the first step.
+        assert_eq!(display.step_fade(&clock), Ok(true));
+        assert_eq!(display.brightness(), 255);
+        now.set(1500);
+        assert_eq!(display.step_fade(&clock), Ok(true));
+        assert_eq!(display.brightness(), 128);
+        now.set(2000);
+        assert_eq!(display.step_fade(&clock), Ok(false));
+        assert_eq!(display.brightness(), 0);
+        assert!(!display.is_fading());
+    }
+
+    #[test]
+    fn only_refreshes_shifting_frame_data_are_counted() {
+        let (mut display, _) = driver::<1, 1>();
+        display.update().unwrap();
+        display.sleep().unwrap();
+        display.update().unwrap();
+        display.wake();
+        display.self_test(&mut NoDelay, 1).unwrap();
+        display.reconfigure(1, 1, ChainOrder::RowMajor).unwrap();
+        display.update().unwrap();
+        assert_eq!(display.stats().refreshes, 2);
+    }
+
+    #[test]
+    fn brightness_skips_refreshes_without_pwm() {
+        let (mut display, spi) = driver::<1, 1>();
+        display.set_pixel(0, 0, true);
+        display.set_brightness(64).unwrap();
+        let lit = (0..256)
+            .filter(|_| refresh(&mut display, &spi)[0][3] == 0x7f)
+            .count();
+        assert_eq!(lit, 64);
+    }
+
+    /// Chain index of the panel at `col`, `row` of a 2 x 2 layout: the panel whose share of
+    /// the shifted data its top-left pixel lights, counting from the first byte shifted.
+    fn chain_position(order: ChainOrder, col: usize, row: usize) -> usize {
+        let (mut display, spi) = driver::<2, 2>();
+        display.set_chain_order(order);
+        display.set_pixel(col * 32, row * 16, true);
+        display.update().unwrap();
+        let bytes = spi.take();
+        let lit = bytes.iter().position(|&byte| byte != 0xff).unwrap();
+        lit % (4 * 16) / 16
+    }
+
+    #[test]
+    fn chain_starts_at_the_far_end() {
+        let cases = [
+            (ChainOrder::RowMajor, [[0, 1], [2, 3]]),
+            (ChainOrder::Serpentine, [[0, 1], [3, 2]]),
+            (ChainOrder::ColumnMajor, [[0, 2], [1, 3]]),
+        ];
+        for (order, indices) in cases {
+            for (row, indices) in indices.iter().enumerate() {
+                for (col, &index) in indices.iter().enumerate() {
+                    assert_eq!(chain_position(order, col, row), index, "{order:?}");
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,224 @@
+//! Scrolling text zones.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget, geometry::Point, pixelcolor::BinaryColor, primitives::Rectangle,
+    Drawable,
+};
+
+use crate::{
+    text::{Text, TextStyle},
+    Clipped,
+};
+
+/// Direction the text moves in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Outcome of [`Marquee::tick`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Nothing changed, the marquee is paused or waiting for its next step.
+    Idle,
+    /// The text moved by one pixel.
+    Moved,
+    /// The text has fully scrolled through the zone the given number of times in total
+    /// and starts over, so a scheduler can rotate messages.
+    LoopCompleted(u32),
+}
+
+/// Text scrolling through a zone of the display.
+///
+/// Several marquees with different zones, speeds and directions can run side by side,
+/// e.g. a static title on top and a ticker below, see [`tick_all`].
+#[derive(Clone, Copy, Debug)]
+pub struct Marquee<'a, 'f> {
+    zone: Rectangle,
+    text: &'a str,
+    style: TextStyle<'f>,
+    direction: Direction,
+    ticks_per_step: u16,
+    ticks: u16,
+    offset: u32,
+    loops: u32,
+    paused: bool,
+    dirty: bool,
+}
+
+impl<'a, 'f> Marquee<'a, 'f> {
+    pub fn new(zone: Rectangle, text: &'a str, style: TextStyle<'f>, direction: Direction) -> Self {
+        Self {
+            zone,
+            text,
+            style,
+            direction,
+            ticks_per_step: 1,
+            ticks: 0,
+            offset: 0,
+            loops: 0,
+            paused: false,
+            dirty: true,
+        }
+    }
+
+    /// Move one pixel every `ticks_per_step` ticks.
+    pub fn with_speed(mut self, ticks_per_step: u16) -> Self {
+        self.ticks_per_step = ticks_per_step.max(1);
+        self
+    }
+
+    pub fn zone(&self) -> Rectangle {
+        self.zone
+    }
+
+    /// Replace the text and start scrolling it from the beginning.
+    pub fn set_text(&mut self, text: &'a str) {
+        self.text = text;
+        self.restart();
+    }
+
+    /// Scroll the text from the beginning and reset the loop count.
+    pub fn restart(&mut self) {
+        self.offset = 0;
+        self.ticks = 0;
+        self.loops = 0;
+        self.dirty = true;
+    }
+
+    /// Stop moving on [`tick`](Self::tick) until [`resume`](Self::resume).
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Scroll offset in pixels, `0` is the text just entering the zone.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Jump to `offset`, wrapped to the scroll period.
+    pub fn seek(&mut self, offset: u32) {
+        self.offset = offset % self.period().max(1);
+        self.ticks = 0;
+        self.dirty = true;
+    }
+
+    /// Number of times the text has fully scrolled through the zone.
+    pub fn loops(&self) -> u32 {
+        self.loops
+    }
+
+    /// Whether the zone has to be redrawn.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Pixels to travel until the text has completely crossed the zone.
+    fn period(&self) -> u32 {
+        match self.direction {
+            Direction::Left | Direction::Right => {
+                self.style.text_width(self.text) + self.zone.size.width
+            }
+            Direction::Up | Direction::Down => {
+                self.style.font.height as u32 + self.zone.size.height
+            }
+        }
+    }
+
+    /// Advance the animation by one tick.
+    pub fn tick(&mut self) -> Event {
+        if self.paused {
+            return Event::Idle;
+        }
+        self.ticks += 1;
+        if self.ticks < self.ticks_per_step {
+            return Event::Idle;
+        }
+        self.ticks = 0;
+        self.offset = (self.offset + 1) % self.period().max(1);
+        self.dirty = true;
+        if self.offset == 0 {
+            self.loops = self.loops.wrapping_add(1);
+            Event::LoopCompleted(self.loops)
+        } else {
+            Event::Moved
+        }
+    }
+
+    fn position(&self) -> Point {
+        let Point { x, y } = self.zone.top_left;
+        let (w, h) = (self.zone.size.width as i32, self.zone.size.height as i32);
+        let offset = self.offset as i32;
+        let anchor = self.style.anchor(&self.zone);
+        let text_width = self.style.text_width(self.text) as i32;
+        let height = self.style.font.height as i32;
+        let top_left = match self.direction {
+            Direction::Left => Point::new(x + w - offset, anchor.y),
+            Direction::Right => Point::new(x - text_width + offset, anchor.y),
+            Direction::Up => Point::new(anchor.x, y + h - offset),
+            Direction::Down => Point::new(anchor.x, y - height + offset),
+        };
+        // The scrolling axis is a top-left coordinate, cancel the alignment `Text` applies to it.
+        let aligned = self.style.top_left(self.text, Point::zero());
+        match self.direction {
+            Direction::Left | Direction::Right => top_left - Point::new(aligned.x, 0),
+            Direction::Up | Direction::Down => top_left - Point::new(0, aligned.y),
+        }
+    }
+
+    /// Clear the zone and draw the text at its current offset.
+    pub fn draw<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let mut target = Clipped::new(target, self.zone);
+        target.fill_solid(&self.zone, self.style.color.invert())?;
+        Text::new(self.text, self.position(), self.style).draw(&mut target)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Tick every marquee and redraw only the zones that changed. Returns the number of
+/// redrawn zones.
+pub fn tick_all<D>(marquees: &mut [Marquee<'_, '_>], target: &mut D) -> Result<usize, D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    tick_all_with(marquees, target, |_, _| {})
+}
+
+/// Like [`tick_all`], calling `on_loop` with the index of a marquee and its loop count
+/// whenever it completes a loop.
+pub fn tick_all_with<D>(
+    marquees: &mut [Marquee<'_, '_>],
+    target: &mut D,
+    mut on_loop: impl FnMut(usize, u32),
+) -> Result<usize, D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let mut redrawn = 0;
+    for (index, marquee) in marquees.iter_mut().enumerate() {
+        if let Event::LoopCompleted(loops) = marquee.tick() {
+            on_loop(index, loops);
+        }
+        if marquee.is_dirty() {
+            marquee.draw(target)?;
+            redrawn += 1;
+        }
+    }
+    Ok(redrawn)
+}
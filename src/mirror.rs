@@ -0,0 +1,185 @@
+//! Live copy of the framebuffer for watching the sign from a debug host.
+//!
+//! [`Mirror`] periodically sends the packed framebuffer to a [`MirrorSink`], with the `rtt`
+//! feature e.g. an RTT up-channel read by probe-rs. Each record is
+//!
+//! | bytes | content |
+//! |-------|---------|
+//! | 4     | [`MAGIC`] |
+//! | 2     | width in pixels, little-endian |
+//! | 2     | height in pixels, little-endian |
+//! | 2     | sequence number, little-endian |
+//! | width * height / 8 | rows top to bottom, leftmost pixel in the MSB, 1 = lit |
+//!
+//! With the `std` feature, [`Decoder`] turns the byte stream back into frames on the host.
+
+use embedded_hal::digital::OutputPin;
+
+use crate::{BufferStorage, Clock, EnableLine, Frame, P10Led, PanelGeometry, RowSelector};
+
+/// Start of every record.
+pub const MAGIC: [u8; 4] = *b"P10M";
+
+const HEADER_BYTES: usize = 10;
+/// Largest payload the decoder accepts, anything bigger is taken for a damaged header.
+#[cfg(feature = "std")]
+const MAX_PAYLOAD: usize = 8192;
+
+/// Byte channel to the debug host.
+pub trait MirrorSink {
+    /// Write `bytes`, returning how many were accepted. A record is written as header and
+    /// payload, the decoder cannot recover a record missing part of either.
+    fn write(&mut self, bytes: &[u8]) -> usize;
+}
+
+#[cfg(feature = "rtt")]
+impl MirrorSink for rtt_target::UpChannel {
+    fn write(&mut self, bytes: &[u8]) -> usize {
+        rtt_target::UpChannel::write(self, bytes)
+    }
+}
+
+/// Rate-limited publisher of the framebuffer.
+#[derive(Clone, Copy, Debug)]
+pub struct Mirror {
+    interval_us: u32,
+    last_us: Option<u32>,
+    sequence: u16,
+}
+
+impl Mirror {
+    /// Publish at most once every `interval_us` microseconds.
+    pub const fn new(interval_us: u32) -> Self {
+        Self {
+            interval_us,
+            last_us: None,
+            sequence: 0,
+        }
+    }
+
+    /// Send the framebuffer of `display` to `sink` if the interval has elapsed.
+    /// Returns whether a complete record was sent.
+    pub fn poll<SPI, E, R, L, const PX: usize, const PY: usize, MODE, G, BUF>(
+        &mut self,
+        display: &P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>,
+        clock: &impl Clock,
+        sink: &mut impl MirrorSink,
+    ) -> bool
+    where
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    {
+        let now = clock.now_us();
+        if self
+            .last_us
+            .is_some_and(|last| now.wrapping_sub(last) < self.interval_us)
+        {
+            return false;
+        }
+        self.last_us = Some(now);
+
+        let mut frame = Frame::<PX, PY, G>::new();
+        display.store_frame(&mut frame);
+        let payload = frame.as_bytes();
+        let mut header = [0; HEADER_BYTES];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..6].copy_from_slice(&(Frame::<PX, PY, G>::WIDTH as u16).to_le_bytes());
+        header[6..8].copy_from_slice(&(Frame::<PX, PY, G>::HEIGHT as u16).to_le_bytes());
+        header[8..10].copy_from_slice(&self.sequence.to_le_bytes());
+        self.sequence = self.sequence.wrapping_add(1);
+        sink.write(&header) == HEADER_BYTES && sink.write(payload) == payload.len()
+    }
+}
+
+#[cfg(feature = "std")]
+pub use host::{Decoder, MirroredFrame};
+
+#[cfg(feature = "std")]
+mod host {
+    use std::{string::String, vec::Vec};
+
+    use super::{HEADER_BYTES, MAGIC, MAX_PAYLOAD};
+
+    /// Frame received from a [`Mirror`](super::Mirror).
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct MirroredFrame {
+        pub width: usize,
+        pub height: usize,
+        pub sequence: u16,
+        /// Rows top to bottom, leftmost pixel in the MSB.
+        pub bits: Vec<u8>,
+    }
+
+    impl MirroredFrame {
+        pub fn get(&self, x: usize, y: usize) -> bool {
+            x < self.width
+                && y < self.height
+                && self.bits[(x + y * self.width) / 8] & (0x80 >> (x % 8)) != 0
+        }
+
+        /// Text rendering with `#` for lit and `.` for dark pixels, one line per row.
+        pub fn render(&self) -> String {
+            let mut out = String::with_capacity((self.width + 1) * self.height);
+            for y in 0..self.height {
+                out.extend((0..self.width).map(|x| if self.get(x, y) { '#' } else { '.' }));
+                out.push('\n');
+            }
+            out
+        }
+    }
+
+    /// Reassembles [`MirroredFrame`]s from the bytes read from the debug probe, skipping
+    /// damaged records.
+    #[derive(Clone, Debug, Default)]
+    pub struct Decoder {
+        buf: Vec<u8>,
+    }
+
+    impl Decoder {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn push(&mut self, bytes: &[u8]) {
+            self.buf.extend_from_slice(bytes);
+        }
+
+        /// Next complete frame, `None` until more bytes are pushed.
+        pub fn next_frame(&mut self) -> Option<MirroredFrame> {
+            loop {
+                let Some(start) = self.buf.windows(4).position(|w| w == MAGIC) else {
+                    // Keep a possible partial magic at the end.
+                    let keep = self.buf.len().min(MAGIC.len() - 1);
+                    self.buf.drain(..self.buf.len() - keep);
+                    return None;
+                };
+                self.buf.drain(..start);
+                if self.buf.len() < HEADER_BYTES {
+                    return None;
+                }
+                let field = |i: usize| u16::from_le_bytes([self.buf[i], self.buf[i + 1]]);
+                let (width, height, sequence) = (field(4) as usize, field(6) as usize, field(8));
+                let len = width * height / 8;
+                if len == 0 || len > MAX_PAYLOAD {
+                    // Not a header after all, resync on the next magic.
+                    self.buf.drain(..1);
+                    continue;
+                }
+                if self.buf.len() < HEADER_BYTES + len {
+                    return None;
+                }
+                let bits = self.buf[HEADER_BYTES..HEADER_BYTES + len].to_vec();
+                self.buf.drain(..HEADER_BYTES + len);
+                return Some(MirroredFrame {
+                    width,
+                    height,
+                    sequence,
+                    bits,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Bus, pin and delay doubles for the unit tests, recording what the driver shifts out.
+
+use core::{cell::RefCell, convert::Infallible};
+use std::{rc::Rc, vec::Vec};
+
+use embedded_hal::{delay::DelayNs, digital, spi};
+
+use crate::{AddressPins, P10Led};
+
+/// Driver on the doubles with all four row address lines.
+pub type Driver<const PX: usize = 1, const PY: usize = 1> =
+    P10Led<Spi, Pin, AddressPins<Pin, Pin, Pin, Pin, 4>, Pin, PX, PY>;
+
+/// [`SpiBus`](spi::SpiBus) keeping every byte written.
+#[derive(Clone, Default)]
+pub struct Spi {
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Spi {
+    /// Bytes written since the last call.
+    pub fn take(&self) -> Vec<u8> {
+        self.bytes.take()
+    }
+}
+
+impl spi::ErrorType for Spi {
+    type Error = Infallible;
+}
+
+impl spi::SpiBus for Spi {
+    fn read(&mut self, _: &mut [u8]) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        self.bytes.borrow_mut().extend_from_slice(words);
+        Ok(())
+    }
+
+    fn transfer(&mut self, _: &mut [u8], words: &[u8]) -> Result<(), Infallible> {
+        self.write(words)
+    }
+
+    fn transfer_in_place(&mut self, _: &mut [u8]) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Every operation of a transaction goes to the bus, the chip select is not modelled.
+impl spi::SpiDevice for Spi {
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), Infallible> {
+        for operation in operations {
+            if let spi::Operation::Write(words) | spi::Operation::Transfer(_, words) = operation {
+                spi::SpiBus::write(self, words)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`OutputPin`](digital::OutputPin) ignoring its level.
+#[derive(Clone, Default)]
+pub struct Pin;
+
+impl digital::ErrorType for Pin {
+    type Error = Infallible;
+}
+
+impl digital::OutputPin for Pin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// [`DelayNs`] returning right away.
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _: u32) {}
+}
+
+/// Driver with the default options and the bus it writes to.
+pub fn driver<const PX: usize, const PY: usize>() -> (Driver<PX, PY>, Spi) {
+    let spi = Spi::default();
+    let driver = P10Led::new(spi.clone(), Pin, Pin, Pin, Pin).unwrap();
+    (driver.with_address_lines(Pin, Pin), spi)
+}
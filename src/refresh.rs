@@ -0,0 +1,332 @@
+//! Refresh sequences shared by the blocking and async drivers.
+//!
+//! Each sequence is written once as an `async fn` over [`Transfer`] and [`Pause`], the only
+//! parts that differ between the modes. The async driver awaits them; the blocking driver
+//! runs them with [`ready`], as its bus writes and delays finish before returning.
+
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use embedded_hal::{digital::OutputPin, spi::SpiBus};
+
+use crate::{
+    Blocking, BufferStorage, CameraRefresh, Clock, EnableLine, Error, P10Led, PanelGeometry,
+    RowSelector, SoftDimming, BLANK,
+};
+
+/// SPI side of a driver mode. Only named inside the crate, so the futures need no `Send` bound.
+#[allow(async_fn_in_trait)]
+pub trait Transfer<MODE> {
+    async fn write(&mut self, words: &[u8]) -> Result<(), Error>;
+
+    /// Wait until the data written so far has left the bus.
+    async fn flush(&mut self) -> Result<(), Error>;
+}
+
+impl<SPI: SpiBus> Transfer<Blocking> for SPI {
+    async fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        SpiBus::write(self, words).map_err(Error::spi)
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        SpiBus::flush(self).map_err(Error::spi)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<SPI: embedded_hal_async::spi::SpiDevice> Transfer<crate::Async> for SPI {
+    async fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        embedded_hal_async::spi::SpiDevice::write(self, words)
+            .await
+            .map_err(Error::spi)
+    }
+
+    /// Every write is a complete transaction, there is nothing left to flush.
+    async fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Delay provider of a driver mode.
+#[allow(async_fn_in_trait)]
+pub trait Pause<MODE> {
+    async fn pause_us(&mut self, us: u32);
+}
+
+impl<D: embedded_hal::delay::DelayNs> Pause<Blocking> for D {
+    async fn pause_us(&mut self, us: u32) {
+        self.delay_us(us);
+    }
+}
+
+#[cfg(feature = "async")]
+impl<D: embedded_hal_async::delay::DelayNs> Pause<crate::Async> for D {
+    async fn pause_us(&mut self, us: u32) {
+        self.delay_us(us).await;
+    }
+}
+
+/// Run a sequence of the blocking driver to completion. Blocking transfers and delays never
+/// leave it pending, so a single poll finishes it.
+pub(crate) fn ready<T>(sequence: impl Future<Output = T>) -> T {
+    let mut sequence = pin!(sequence);
+    match sequence
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("blocking refresh sequences do not wait"),
+    }
+}
+
+impl<
+        SPI: Transfer<MODE>,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    pub(crate) async fn send_cache(&mut self) -> Result<(), Error> {
+        if self.blanked {
+            return Ok(());
+        }
+        if self.shift_blanking {
+            self.set_output(false)?;
+        }
+        let chunk_len = self.panel_scan_bytes();
+        let range = self.scan_data_range(self.scan_row as usize);
+        let cache = &self
+            .buffers
+            .as_ref()
+            .cache
+            .as_flattened()
+            .as_flattened()
+            .as_flattened()[range];
+        if !self.skips_refreshes() {
+            return self.spi.write(cache).await;
+        }
+        let chunks = cache.chunks(chunk_len);
+        for (panel, chunk) in chunks.enumerate() {
+            let data = if !self.panel_visible(panel) {
+                &BLANK[..chunk.len()]
+            } else {
+                chunk
+            };
+            self.spi.write(data).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_blank(&mut self) -> Result<(), Error> {
+        if self.blanked {
+            return Ok(());
+        }
+        for _ in 0..self.panels() {
+            let blank = &BLANK[..self.panel_scan_bytes()];
+            self.spi.write(blank).await?;
+        }
+        Ok(())
+    }
+
+    /// Disable the output and latch blank data into the chain.
+    pub(crate) async fn blank_output(&mut self) -> Result<(), Error> {
+        self.set_output(false)?;
+        self.send_blank().await?;
+        self.latch.set_high().map_err(Error::digital)?; // Latch DMD shift register output
+        self.latch.set_low().map_err(Error::digital)?; // (Deliberately left as digitalWrite to ensure decent latching time)
+        Ok(())
+    }
+
+    /// Blank the output at the end of a scan and count the refresh, unless the display is
+    /// off and the scan shifted no frame data.
+    async fn finish_refresh(&mut self) -> Result<(), Error> {
+        self.blank_output().await?;
+        if !self.blanked {
+            self.count_refresh();
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn scan(&mut self) -> Result<(), Error> {
+        for _ in 0..self.scan_rows() {
+            self.fill_cache();
+            self.send_cache().await?;
+
+            self.next_row()?;
+        }
+        self.fill_cache();
+        self.send_cache().await?;
+
+        self.finish_refresh().await
+    }
+
+    pub(crate) async fn scan_limited(&mut self, clock: &impl Clock) -> Result<bool, Error> {
+        if !self.flush_due(clock) {
+            return Ok(false);
+        }
+        self.scan().await?;
+        Ok(true)
+    }
+
+    pub(crate) async fn scan_adaptive(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl Pause<MODE>,
+    ) -> Result<(), Error> {
+        let gap = self.idle_gap_us(clock);
+        self.scan().await?;
+        if let Some(gap) = gap {
+            delay.pause_us(gap).await;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn scan_profiled(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl Pause<MODE>,
+    ) -> Result<(), Error> {
+        let gap = self.idle_gap_us(clock);
+        let rebuild = self.flush_due(clock);
+        let hold = self.profile.row_hold_us();
+        for _ in 0..self.scan_rows() {
+            if rebuild {
+                self.fill_cache();
+            }
+            self.send_cache().await?;
+
+            self.next_row()?;
+            if hold != 0 {
+                delay.pause_us(hold).await;
+            }
+        }
+        self.finish_refresh().await?;
+        if let Some(gap) = gap {
+            delay.pause_us(gap).await;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn scan_camera(
+        &mut self,
+        delay: &mut impl Pause<MODE>,
+        camera: CameraRefresh,
+        mut on_row: impl FnMut(u8),
+    ) -> Result<(), Error> {
+        for _ in 0..camera.passes {
+            for _ in 0..self.scan_rows() {
+                self.fill_cache();
+                self.send_cache().await?;
+
+                self.next_row()?;
+                on_row((self.scan_row + self.scan_rows() - 1) % self.scan_rows());
+                delay.pause_us(camera.row_hold_us).await;
+            }
+        }
+        self.finish_refresh().await
+    }
+
+    pub(crate) async fn scan_dimmed(
+        &mut self,
+        delay: &mut impl Pause<MODE>,
+        dimming: SoftDimming,
+    ) -> Result<(), Error> {
+        self.fill_cache();
+        self.send_cache().await?;
+        let rows = self.scan_rows();
+        for row in 0..rows {
+            self.next_row()?;
+            delay.pause_us(dimming.on_us).await;
+            self.set_output(false)?;
+            if row + 1 != rows {
+                self.fill_cache();
+                self.send_cache().await?;
+            }
+            delay.pause_us(dimming.off_us).await;
+        }
+
+        self.finish_refresh().await
+    }
+
+    pub(crate) async fn scan_paced(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl Pause<MODE>,
+    ) -> Result<(), Error> {
+        let Some(period) = self.row_period_us() else {
+            return self.scan().await;
+        };
+        self.next_row_us = None;
+        for _ in 0..self.scan_rows() {
+            self.fill_cache();
+            self.send_cache().await?;
+
+            self.next_row()?;
+            if let Some(remaining) = self.next_row_deadline(clock, period) {
+                delay.pause_us(remaining).await;
+            }
+        }
+        self.next_row_us = None;
+        self.finish_refresh().await
+    }
+
+    pub(crate) async fn scan_row(&mut self) -> Result<(), Error> {
+        self.fill_cache();
+        self.send_cache().await?;
+        self.next_row()?;
+        if self.scan_row == 0 && !self.blanked {
+            self.count_refresh();
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn scan_row_paced(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl Pause<MODE>,
+    ) -> Result<(), Error> {
+        self.scan_row().await?;
+        if let Some(period) = self.row_period_us() {
+            if let Some(remaining) = self.next_row_deadline(clock, period) {
+                delay.pause_us(remaining).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn scan_timed(&mut self, clock: &impl Clock) -> Result<(), Error> {
+        let start = clock.now_us();
+        self.scan().await?;
+        self.record_scan(clock.now_us().wrapping_sub(start));
+        self.sample_refresh_rate(clock);
+        Ok(())
+    }
+
+    pub(crate) async fn scan_retained(&mut self) -> Result<(), Error> {
+        for _ in 0..self.scan_rows() {
+            self.send_cache().await?;
+
+            self.next_row()?;
+        }
+        self.send_cache().await?;
+
+        self.finish_refresh().await
+    }
+
+    pub(crate) async fn enter_sleep(&mut self) -> Result<(), Error> {
+        self.blank_output().await?;
+        self.display_off()?;
+        self.latch.set_low().map_err(Error::digital)?;
+        self.scan_row = 0;
+        self.rows.select_row(0).map_err(Error::digital)?;
+        self.spi.flush().await
+    }
+}
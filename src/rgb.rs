@@ -0,0 +1,351 @@
+//! Driver for full-color HUB75 modules, the RGB siblings of the P10.
+//!
+//! The scan works like on the mono panels: the data of one scan row is shifted into the chain,
+//! latched and shown while the [`RowSelector`] lights the row. A HUB75 module lights two rows
+//! per scan row, one in each half of the panel, fed by the R1/G1/B1 and R2/G2/B2 data lines,
+//! so a 16 row panel is a 1/8 scan one with address lines A to C. Colors are shown by binary
+//! code modulation: every bit plane of a scan row is lit for a time weighted by its bit.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::{Rgb565, Rgb888, RgbColor},
+    Pixel,
+};
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{OutputPin, PinState},
+};
+
+use crate::{EnablePolarity, Error, Panel32x16, PanelGeometry, RowSelector};
+
+/// Pixels of a panel, the storage reserved for each panel of the chain.
+const PANEL_PIXELS: usize = 512;
+
+/// Bit of each data line in the column bytes written to an [`RgbBus`].
+pub const R1: u8 = 1 << 0;
+pub const G1: u8 = 1 << 1;
+pub const B1: u8 = 1 << 2;
+pub const R2: u8 = 1 << 3;
+pub const G2: u8 = 1 << 4;
+pub const B2: u8 = 1 << 5;
+
+/// The six data lines of a HUB75 chain clocked together.
+///
+/// Each byte of `columns` is one clock, with the lines set as given by [`R1`] to [`B2`].
+/// A GPIO port written in one go, a PIO or DMA to a port can implement it directly;
+/// [`BitBangRgb`] drives single pins.
+pub trait RgbBus {
+    fn write_columns(&mut self, columns: &[u8]) -> Result<(), Error>;
+}
+
+/// [`RgbBus`] bit-banged on the clock and the six data pins, data sampled on the rising edge.
+pub struct BitBangRgb<CLK, P> {
+    clock: CLK,
+    /// R1, G1, B1, R2, G2, B2.
+    data: [P; 6],
+}
+
+impl<CLK: OutputPin, P: OutputPin> BitBangRgb<CLK, P> {
+    /// Create a bus from the clock and the data pins R1, G1, B1, R2, G2, B2, e.g. degraded to
+    /// one pin type by the HAL.
+    pub fn new(clock: CLK, data: [P; 6]) -> Self {
+        Self { clock, data }
+    }
+
+    pub fn release(self) -> (CLK, [P; 6]) {
+        (self.clock, self.data)
+    }
+}
+
+impl<CLK: OutputPin, P: OutputPin> RgbBus for BitBangRgb<CLK, P> {
+    fn write_columns(&mut self, columns: &[u8]) -> Result<(), Error> {
+        for &column in columns {
+            for (line, pin) in self.data.iter_mut().enumerate() {
+                pin.set_state(PinState::from(column & (1 << line) != 0))
+                    .map_err(Error::digital)?;
+            }
+            self.clock.set_high().map_err(Error::digital)?;
+            self.clock.set_low().map_err(Error::digital)?;
+        }
+        Ok(())
+    }
+}
+
+/// Driver for a `PX` x `PY` chain of HUB75 panels of geometry `G`.
+///
+/// Panels are chained like the mono ones in the default [`ChainOrder`](crate::ChainOrder),
+/// with the top left panel first. The framebuffer holds [`Rgb565`] pixels; draw
+/// [`Rgb888`] content through [`as_rgb888`](Self::as_rgb888).
+pub struct RgbP10<BUS, E, R, L, const PX: usize = 1, const PY: usize = 1, G = Panel32x16> {
+    bus: BUS,
+    enable: E,
+    rows: R,
+    latch: L,
+    pixels: [[[Rgb565; PANEL_PIXELS]; PX]; PY],
+    depth: u8,
+    hold_us: u32,
+    enable_polarity: EnablePolarity,
+    _geometry: core::marker::PhantomData<G>,
+}
+
+impl<
+        BUS: RgbBus,
+        E: OutputPin,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > RgbP10<BUS, E, R, L, PX, PY, G>
+{
+    const PANEL_WIDTH: usize = G::WIDTH;
+    const PANEL_HEIGHT: usize = G::HEIGHT;
+    pub const WIDTH: usize = PX * Self::PANEL_WIDTH;
+    pub const HEIGHT: usize = PY * Self::PANEL_HEIGHT;
+
+    /// Evaluated by the constructor, so an unusable layout fails to compile.
+    const LAYOUT_CHECK: () = {
+        assert!(
+            PX != 0 && PY != 0,
+            "RgbP10 needs at least one panel in each direction"
+        );
+        assert!(
+            G::WIDTH != 0 && G::HEIGHT != 0 && G::HEIGHT % 2 == 0,
+            "PanelGeometry of a HUB75 panel needs an even height"
+        );
+        assert!(
+            G::WIDTH * G::HEIGHT <= PANEL_PIXELS,
+            "PanelGeometry larger than the 512 pixels of a stock panel"
+        );
+        assert!(
+            G::HEIGHT / 2 <= 1 << R::LINES,
+            "RowSelector has too few address lines for the scan rows of the panel"
+        );
+    };
+
+    /// Create a driver with every pixel off, showing 1 bit per color.
+    ///
+    /// `rows` selects the scan rows, e.g. [`AddressPins`](crate::AddressPins) with C for a 16
+    /// row panel. The enable (OE) pin of HUB75 modules is active low.
+    pub fn new(bus: BUS, enable: E, rows: R, latch: L) -> Self {
+        let () = Self::LAYOUT_CHECK;
+        Self {
+            bus,
+            enable,
+            rows,
+            latch,
+            pixels: [[[Rgb565::BLACK; PANEL_PIXELS]; PX]; PY],
+            depth: 1,
+            hold_us: 20,
+            enable_polarity: EnablePolarity::ActiveLow,
+            _geometry: core::marker::PhantomData,
+        }
+    }
+
+    pub fn release(self) -> (BUS, E, R, L) {
+        (self.bus, self.enable, self.rows, self.latch)
+    }
+
+    /// Set the bits shown per color, `1..=5`, clamped. Each bit adds a bit plane to the scan,
+    /// doubling the refresh time of the previous ones.
+    pub fn set_color_depth(&mut self, bits: u8) {
+        self.depth = bits.clamp(1, 5);
+    }
+
+    pub fn color_depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Set how long the least significant bit plane of a scan row is lit.
+    pub fn set_plane_hold_us(&mut self, us: u32) {
+        self.hold_us = us;
+    }
+
+    pub fn set_enable_polarity(&mut self, polarity: EnablePolarity) {
+        self.enable_polarity = polarity;
+    }
+
+    /// Set the pixel `x`, `y`, ignored outside the display.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Rgb565) {
+        if let Some(pixel) = self.pixel_mut(x, y) {
+            *pixel = color;
+        }
+    }
+
+    /// Color of the pixel `x`, `y`, black outside the display.
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb565 {
+        if x >= Self::WIDTH || y >= Self::HEIGHT {
+            return Rgb565::BLACK;
+        }
+        let (w, h) = (Self::PANEL_WIDTH, Self::PANEL_HEIGHT);
+        self.pixels[y / h][x / w][y % h * w + x % w]
+    }
+
+    fn pixel_mut(&mut self, x: usize, y: usize) -> Option<&mut Rgb565> {
+        if x >= Self::WIDTH || y >= Self::HEIGHT {
+            return None;
+        }
+        let (w, h) = (Self::PANEL_WIDTH, Self::PANEL_HEIGHT);
+        Some(&mut self.pixels[y / h][x / w][y % h * w + x % w])
+    }
+
+    /// Set every pixel to `color`.
+    pub fn fill(&mut self, color: Rgb565) {
+        self.pixels
+            .as_flattened_mut()
+            .as_flattened_mut()
+            .fill(color);
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(Rgb565::BLACK);
+    }
+
+    /// Draw target taking [`Rgb888`] colors, reduced to the [`Rgb565`] framebuffer.
+    pub fn as_rgb888(&mut self) -> Rgb888Target<'_, BUS, E, R, L, PX, PY, G> {
+        Rgb888Target { driver: self }
+    }
+
+    fn set_output(&mut self, on: bool) -> Result<(), Error> {
+        let high = on == (self.enable_polarity == EnablePolarity::ActiveHigh);
+        self.enable
+            .set_state(PinState::from(high))
+            .map_err(Error::digital)
+    }
+
+    /// Shift out bit `bit` of the 5 bit channels of scan row `row`.
+    fn send_plane(&mut self, row: usize, bit: u8) -> Result<(), Error> {
+        let (w, half) = (Self::PANEL_WIDTH, Self::PANEL_HEIGHT / 2);
+        let lit = |color: Rgb565| {
+            // Green has one bit more than red and blue, its LSB is dropped.
+            let channels = [color.r(), color.g() >> 1, color.b()];
+            channels
+                .iter()
+                .enumerate()
+                .fold(0, |lines, (n, &c)| lines | (((c >> bit) & 1) << n))
+        };
+        let mut columns = [0; PANEL_PIXELS / 2];
+        for py in 0..PY {
+            for px in 0..PX {
+                let panel = &self.pixels[py][px];
+                let (upper, lower) = (&panel[row * w..][..w], &panel[(row + half) * w..][..w]);
+                for ((column, &top), &bottom) in columns.iter_mut().zip(upper).zip(lower) {
+                    *column = lit(top) | lit(bottom) << 3;
+                }
+                self.bus.write_columns(&columns[..w])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Show the framebuffer once, all scan rows with every bit plane, then turn the output
+    /// off. Call it continuously, e.g. from the main loop.
+    pub fn update(&mut self, delay: &mut impl DelayNs) -> Result<(), Error> {
+        let rows = Self::PANEL_HEIGHT / 2;
+        for row in 0..rows {
+            for plane in 0..self.depth {
+                let bit = 5 - self.depth + plane;
+                self.send_plane(row, bit)?;
+                self.set_output(false)?;
+                self.rows.select_row(row as u8).map_err(Error::digital)?;
+                self.latch.set_high().map_err(Error::digital)?;
+                self.latch.set_low().map_err(Error::digital)?;
+                self.set_output(true)?;
+                delay.delay_us(self.hold_us << plane);
+            }
+        }
+        self.set_output(false)
+    }
+}
+
+impl<
+        BUS: RgbBus,
+        E: OutputPin,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > DrawTarget for RgbP10<BUS, E, R, L, PX, PY, G>
+{
+    type Color = Rgb565;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if pos.x >= 0 && pos.y >= 0 {
+                self.set_pixel(pos.x as _, pos.y as _, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(color);
+        Ok(())
+    }
+}
+
+impl<
+        BUS: RgbBus,
+        E: OutputPin,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > OriginDimensions for RgbP10<BUS, E, R, L, PX, PY, G>
+{
+    fn size(&self) -> Size {
+        Size::new(Self::WIDTH as _, Self::HEIGHT as _)
+    }
+}
+
+/// [`Rgb888`] draw target over an [`RgbP10`], see [`RgbP10::as_rgb888`].
+pub struct Rgb888Target<'a, BUS, E, R, L, const PX: usize, const PY: usize, G> {
+    driver: &'a mut RgbP10<BUS, E, R, L, PX, PY, G>,
+}
+
+impl<
+        BUS: RgbBus,
+        E: OutputPin,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > DrawTarget for Rgb888Target<'_, BUS, E, R, L, PX, PY, G>
+{
+    type Color = Rgb888;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.driver.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(pos, color)| Pixel(pos, color.into())),
+        )
+    }
+}
+
+impl<
+        BUS: RgbBus,
+        E: OutputPin,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+    > OriginDimensions for Rgb888Target<'_, BUS, E, R, L, PX, PY, G>
+{
+    fn size(&self) -> Size {
+        self.driver.size()
+    }
+}
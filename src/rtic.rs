@@ -0,0 +1,90 @@
+//! Facade for refreshing the display from an RTIC hardware task.
+//!
+//! Split the driver in `#[init]` with [`split_static`], using a [`FrameChannel`] from the
+//! `local` resources of `init`. Both halves are plain owned values: give the
+//! [`TimerScanner`] to the timer task as a local resource and keep the [`FrameSubmitter`] in
+//! `idle` or a software task. Frames are handed over without locks, so neither side needs
+//! a shared resource or delays the other.
+
+use embedded_hal::{digital::OutputPin, spi::SpiBus};
+
+use crate::{
+    Blocking, BufferStorage, Buffers, Canvas, EnableLine, Error, FrameChannel, P10Led, Panel32x16,
+    PanelGeometry, RowSelector, Scanner,
+};
+
+/// Drawing side, a [`Canvas`] over a channel living for the whole program.
+pub type FrameSubmitter<const PX: usize, const PY: usize, G = Panel32x16> =
+    Canvas<'static, PX, PY, G>;
+
+/// Scanning side for a periodic timer task, see [`on_timer`](Self::on_timer).
+pub struct TimerScanner<
+    SPI,
+    E: EnableLine,
+    R: RowSelector,
+    L: OutputPin,
+    const PX: usize,
+    const PY: usize,
+    G: PanelGeometry + 'static = Panel32x16,
+    BUF: BufferStorage<PX, PY> = Buffers<PX, PY>,
+> {
+    scanner: Scanner<'static, SPI, E, R, L, PX, PY, G, BUF>,
+    last_error: Option<Error>,
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry + 'static,
+        BUF: BufferStorage<PX, PY>,
+    > TimerScanner<SPI, E, R, L, PX, PY, G, BUF>
+{
+    /// Output one scan row, call it on every timer interrupt, once per scan row of a refresh.
+    ///
+    /// A hardware task has nowhere to return errors to, so a failure is recorded for
+    /// [`take_error`](Self::take_error) and the scan carries on with the next interrupt.
+    pub fn on_timer(&mut self) {
+        if let Err(e) = self.scanner.tick() {
+            self.last_error = Some(e);
+        }
+    }
+
+    /// Most recent error of [`on_timer`](Self::on_timer), cleared by the call.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.last_error.take()
+    }
+
+    pub fn driver(&mut self) -> &mut P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF> {
+        self.scanner.driver()
+    }
+}
+
+/// Split `driver` for an RTIC application, see the [module documentation](self).
+#[allow(clippy::type_complexity)]
+pub fn split_static<
+    SPI: SpiBus,
+    E: EnableLine,
+    R: RowSelector,
+    L: OutputPin,
+    const PX: usize,
+    const PY: usize,
+    G: PanelGeometry + 'static,
+    BUF: BufferStorage<PX, PY>,
+>(
+    driver: P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>,
+    channel: &'static mut FrameChannel<PX, PY, G>,
+) -> (
+    FrameSubmitter<PX, PY, G>,
+    TimerScanner<SPI, E, R, L, PX, PY, G, BUF>,
+) {
+    let (canvas, scanner) = driver.split(channel);
+    let scanner = TimerScanner {
+        scanner,
+        last_error: None,
+    };
+    (canvas, scanner)
+}
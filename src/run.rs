@@ -0,0 +1,251 @@
+//! Fixed-timestep animation loop.
+
+use core::ops::ControlFlow;
+
+use embedded_hal::{digital::OutputPin, spi::SpiBus};
+
+use crate::{
+    refresh::{ready, Pause, Transfer},
+    time::remaining_us,
+    Blocking, BufferStorage, Clock, EnableLine, Error, Frame, P10Led, PanelGeometry, RowSelector,
+};
+
+/// Most simulation steps run between two refreshes before the loop drops time,
+/// so a slow step can't make it spiral behind.
+const MAX_CATCH_UP: u32 = 4;
+
+/// Fixed-timestep bookkeeping shared by the blocking and async loops.
+struct Timestep {
+    step_us: u32,
+    last_us: u32,
+    accumulated: u32,
+    tick: u32,
+}
+
+impl Timestep {
+    fn new(clock: &impl Clock, step_us: u32) -> Self {
+        Self {
+            step_us: step_us.max(1),
+            last_us: clock.now_us(),
+            accumulated: 0,
+            tick: 0,
+        }
+    }
+
+    /// Number of steps due at `clock`.
+    fn due(&mut self, clock: &impl Clock) -> u32 {
+        let now = clock.now_us();
+        self.accumulated = self
+            .accumulated
+            .saturating_add(now.wrapping_sub(self.last_us));
+        self.last_us = now;
+        let steps = self.accumulated / self.step_us;
+        self.accumulated %= self.step_us;
+        steps.min(MAX_CATCH_UP)
+    }
+}
+
+impl<
+        SPI: Transfer<MODE>,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    async fn run_steps(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl Pause<MODE>,
+        step_us: u32,
+        period_us: u32,
+        mut step: impl FnMut(u32, &mut Frame<PX, PY, G>) -> ControlFlow<()>,
+    ) -> Result<(), Error> {
+        let mut frame = Frame::new();
+        let mut timestep = Timestep::new(clock, step_us);
+        let mut deadline = clock.now_us();
+        loop {
+            for _ in 0..timestep.due(clock) {
+                let flow = step(timestep.tick, &mut frame);
+                timestep.tick = timestep.tick.wrapping_add(1);
+                self.load_frame(&frame);
+                if flow.is_break() {
+                    return self.scan().await;
+                }
+            }
+            self.scan().await?;
+            deadline = deadline.wrapping_add(period_us);
+            if let Some(remaining) = remaining_us(clock, deadline) {
+                delay.pause_us(remaining).await;
+            } else {
+                // Fell behind by more than a period, restart the schedule from now.
+                deadline = clock.now_us();
+            }
+        }
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>
+{
+    /// Run a fixed-timestep animation loop, e.g. for games and demos.
+    ///
+    /// `step` is called every `step_us` microseconds of `clock` time with the tick number
+    /// and an off-screen frame to draw into; the frame is swapped onto the display after
+    /// each step, so the refresh never shows a half-drawn picture. The display is refreshed
+    /// once per iteration in between, and each iteration is padded with `delay` to
+    /// `period_us`, with deadlines advancing by the period so the pace does not drift; `0`
+    /// refreshes back to back. Returns once `step` breaks.
+    pub fn run_loop(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+        step_us: u32,
+        period_us: u32,
+        step: impl FnMut(u32, &mut Frame<PX, PY, G>) -> ControlFlow<()>,
+    ) -> Result<(), Error> {
+        ready(self.run_steps(clock, delay, step_us, period_us, step))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<
+        SPI: embedded_hal_async::spi::SpiDevice,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, crate::Async, G, BUF>
+{
+    /// Run a fixed-timestep animation loop, see the blocking
+    /// [`run_loop`](P10Led::run_loop).
+    pub async fn run_loop(
+        &mut self,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+        step_us: u32,
+        period_us: u32,
+        step: impl FnMut(u32, &mut Frame<PX, PY, G>) -> ControlFlow<()>,
+    ) -> Result<(), Error> {
+        self.run_steps(clock, delay, step_us, period_us, step).await
+    }
+
+    /// Refresh forever as the body of an executor task, e.g. under embassy with
+    /// `embassy_time::Delay` and `|| Instant::now().as_micros() as u32` as the clock.
+    ///
+    /// One scan row is output every `row_period_us` of `clock` time, with deadlines advancing
+    /// by the period instead of from the last wakeup so the refresh rate does not drift.
+    /// Frames sent through the [`Sender`](crate::Sender) matching `rx` are taken over at the
+    /// start of each refresh. Only returns on a bus or pin error.
+    pub async fn refresh_loop(
+        &mut self,
+        rx: &mut crate::Receiver<'_, PX, PY, G>,
+        clock: &impl Clock,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+        row_period_us: u32,
+    ) -> Result<core::convert::Infallible, Error> {
+        let mut deadline = clock.now_us();
+        loop {
+            if self.scan_phase() == 0 {
+                self.receive_frame(rx);
+            }
+            self.tick().await?;
+            deadline = deadline.wrapping_add(row_period_us);
+            if let Some(remaining) = crate::time::remaining_us(clock, deadline) {
+                delay.delay_us(remaining).await;
+            } else {
+                // Fell behind by more than a period, restart the schedule from now.
+                deadline = clock.now_us();
+            }
+        }
+    }
+
+    /// Ready-made body of an embassy refresh task: one scan row per tick of an
+    /// [`embassy_time::Ticker`], so a full refresh takes `refresh_period`. Frames sent
+    /// through the [`Sender`](crate::Sender) matching `rx` are taken over at the start of
+    /// each refresh. Only returns on a bus or pin error.
+    ///
+    /// Embassy tasks cannot be generic, so spawn a task for the concrete driver type that
+    /// only calls it:
+    ///
+    /// ```ignore
+    /// #[embassy_executor::task]
+    /// async fn refresh(display: &'static mut Display, mut rx: Receiver<'static>) {
+    ///     let _ = display.refresh_task(&mut rx, Duration::from_millis(10)).await;
+    /// }
+    /// ```
+    #[cfg(feature = "embassy")]
+    pub async fn refresh_task(
+        &mut self,
+        rx: &mut crate::Receiver<'_, PX, PY, G>,
+        refresh_period: embassy_time::Duration,
+    ) -> Result<core::convert::Infallible, Error> {
+        let mut ticker = embassy_time::Ticker::every(refresh_period / self.scan_rows() as u32);
+        loop {
+            if self.scan_phase() == 0 {
+                self.receive_frame(rx);
+            }
+            self.tick().await?;
+            ticker.next().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, ops::ControlFlow};
+
+    use embedded_hal::delay::DelayNs;
+
+    use crate::mock::driver;
+
+    /// Delay advancing a simulated clock in microseconds.
+    struct Advance<'a>(&'a Cell<u32>);
+
+    impl DelayNs for Advance<'_> {
+        fn delay_ns(&mut self, ns: u32) {
+            self.0.set(self.0.get() + ns / 1000);
+        }
+
+        fn delay_us(&mut self, us: u32) {
+            self.0.set(self.0.get() + us);
+        }
+    }
+
+    #[test]
+    fn run_loop_paces_each_iteration() {
+        let (mut display, _) = driver::<1, 1>();
+        let now = Cell::new(0);
+        let clock = || now.get();
+        let mut ticks = 0;
+        display
+            .run_loop(&clock, &mut Advance(&now), 1000, 1000, |tick, _| {
+                ticks += 1;
+                if tick == 9 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert_eq!(ticks, 10);
+        // One idle iteration before the first step is due, then one step per period.
+        assert_eq!(now.get(), 10_000);
+        assert_eq!(display.stats().refreshes, 11);
+    }
+}
@@ -0,0 +1,242 @@
+//! Persistence of the sign configuration through one store trait.
+//!
+//! [`PersistentSettings`] connects the store to the driver: [`restore`] applies the stored
+//! settings at boot and [`track`] saves changes made on the driver, e.g. by a brightness
+//! button, once they have settled.
+//!
+//! [`restore`]: PersistentSettings::restore
+//! [`track`]: PersistentSettings::track
+
+use embedded_hal::digital::OutputPin;
+
+use crate::{
+    BufferStorage, Clock, EnableLine, Error, P10Led, PanelGeometry, Rotation, RowSelector,
+};
+
+/// Daily on period, in minutes after midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    pub on_minute: u16,
+    pub off_minute: u16,
+}
+
+impl Schedule {
+    /// Whether the sign should be on at `minute` after midnight. Periods past midnight wrap.
+    pub fn is_on(&self, minute: u16) -> bool {
+        if self.on_minute <= self.off_minute {
+            (self.on_minute..self.off_minute).contains(&minute)
+        } else {
+            minute >= self.on_minute || minute < self.off_minute
+        }
+    }
+}
+
+/// Sign configuration kept across power cycles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// Display brightness, see [`P10Led::set_brightness`].
+    pub brightness: u8,
+    /// Clockwise rotation in quarter turns.
+    pub orientation: u8,
+    /// Page of the application shown after power-up.
+    pub active_page: u8,
+    /// Daily on period, `None` to stay on.
+    pub schedule: Option<Schedule>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            brightness: u8::MAX,
+            orientation: 0,
+            active_page: 0,
+            schedule: None,
+        }
+    }
+}
+
+/// Version tag of the layout written by [`Settings::to_bytes`].
+const VERSION: u8 = 1;
+
+impl Settings {
+    /// Size of the packed form.
+    pub const BYTES: usize = 10;
+
+    /// Pack into a versioned record with a checksum, for stores working on raw bytes.
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        let (enabled, on, off) = match self.schedule {
+            Some(s) => (1, s.on_minute, s.off_minute),
+            None => (0, 0, 0),
+        };
+        let mut bytes = [0; Self::BYTES];
+        bytes[..5].copy_from_slice(&[
+            VERSION,
+            self.brightness,
+            self.orientation,
+            self.active_page,
+            enabled,
+        ]);
+        bytes[5..7].copy_from_slice(&on.to_le_bytes());
+        bytes[7..9].copy_from_slice(&off.to_le_bytes());
+        bytes[9] = checksum(&bytes[..9]);
+        bytes
+    }
+
+    /// Unpack a record written by [`to_bytes`](Self::to_bytes). `None` if it is blank,
+    /// corrupted or from another version.
+    pub fn from_bytes(bytes: &[u8; Self::BYTES]) -> Option<Self> {
+        if bytes[0] != VERSION || bytes[9] != checksum(&bytes[..9]) {
+            return None;
+        }
+        let on_minute = u16::from_le_bytes([bytes[5], bytes[6]]);
+        let off_minute = u16::from_le_bytes([bytes[7], bytes[8]]);
+        Some(Self {
+            brightness: bytes[1],
+            orientation: bytes[2] % 4,
+            active_page: bytes[3],
+            schedule: (bytes[4] != 0).then_some(Schedule {
+                on_minute,
+                off_minute,
+            }),
+        })
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0x5a, |sum: u8, &b| sum.rotate_left(1) ^ b)
+}
+
+/// Non-volatile storage for [`Settings`], e.g. EEPROM or a flash sector.
+pub trait SettingsStore {
+    type Error;
+
+    /// Stored settings, `None` if nothing valid has been saved yet.
+    fn load(&mut self) -> Result<Option<Settings>, Self::Error>;
+
+    fn save(&mut self, settings: &Settings) -> Result<(), Self::Error>;
+}
+
+/// Current [`Settings`] backed by a [`SettingsStore`].
+///
+/// Changes are written back only after they have settled for `debounce_us`, so stepping
+/// through brightness levels with a button does not wear out the flash.
+pub struct PersistentSettings<S> {
+    store: S,
+    settings: Settings,
+    debounce_us: u32,
+    changed_us: Option<u32>,
+}
+
+impl<S: SettingsStore> PersistentSettings<S> {
+    /// Load the settings from `store`, falling back to the defaults.
+    pub fn load(mut store: S, debounce_us: u32) -> Result<Self, S::Error> {
+        let settings = store.load()?.unwrap_or_default();
+        Ok(Self {
+            store,
+            settings,
+            debounce_us,
+            changed_us: None,
+        })
+    }
+
+    pub fn get(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Change the settings at `clock`, to be saved by a later [`poll`](Self::poll).
+    pub fn update(&mut self, clock: &impl Clock, f: impl FnOnce(&mut Settings)) {
+        let before = self.settings;
+        f(&mut self.settings);
+        if self.settings != before {
+            self.changed_us = Some(clock.now_us());
+        }
+    }
+
+    /// Save pending changes once they have settled. Returns whether they were saved.
+    pub fn poll(&mut self, clock: &impl Clock) -> Result<bool, S::Error> {
+        match self.changed_us {
+            Some(changed) if clock.now_us().wrapping_sub(changed) >= self.debounce_us => {
+                self.flush().map(|()| true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Save pending changes right away, e.g. before powering down.
+    pub fn flush(&mut self) -> Result<(), S::Error> {
+        if self.changed_us.is_some() {
+            self.store.save(&self.settings)?;
+            self.changed_us = None;
+        }
+        Ok(())
+    }
+
+    /// Apply the stored settings to `display`, e.g. at boot.
+    pub fn restore<SPI, E, R, L, const PX: usize, const PY: usize, MODE, G, BUF>(
+        &self,
+        display: &mut P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>,
+    ) -> Result<(), Error>
+    where
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    {
+        display.apply_settings(&self.settings)
+    }
+
+    /// Take over the display related settings of `display` and save them once they have
+    /// settled, call it once per main loop iteration. Returns whether they were saved.
+    pub fn track<SPI, E, R, L, const PX: usize, const PY: usize, MODE, G, BUF>(
+        &mut self,
+        display: &P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>,
+        clock: &impl Clock,
+    ) -> Result<bool, S::Error>
+    where
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    {
+        self.update(clock, |settings| display.capture_settings(settings));
+        self.poll(clock)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<
+        SPI,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        MODE,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>
+{
+    /// Apply the display related part of `settings`, e.g. after loading them at boot.
+    ///
+    /// The brightness is applied with [`set_brightness`](Self::set_brightness), so the
+    /// compensation is left alone, the orientation as rotation.
+    pub fn apply_settings(&mut self, settings: &Settings) -> Result<(), Error> {
+        self.set_rotation(Rotation::from_quarter_turns(settings.orientation));
+        self.set_brightness(settings.brightness)
+    }
+
+    /// Copy the display related state into `settings`, the counterpart of
+    /// [`apply_settings`](Self::apply_settings). A fade in progress counts with the level
+    /// it is fading to.
+    pub fn capture_settings(&self, settings: &mut Settings) {
+        settings.brightness = self.target_brightness();
+        settings.orientation = self.rotation().quarter_turns();
+    }
+}
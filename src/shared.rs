@@ -0,0 +1,83 @@
+//! Driver shared between the main loop and a refresh interrupt.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use embedded_hal::{digital::OutputPin, spi::SpiBus};
+
+use crate::{Blocking, BufferStorage, EnableLine, Error, P10Led, PanelGeometry, RowSelector};
+
+/// Driver slot for a `static`, refreshed from a timer interrupt while the main loop draws.
+///
+/// Every access runs inside a critical section, so the refresh cannot interrupt a half-done
+/// drawing operation and any driver method is safe to call from either side. Keep the
+/// closures short, the refresh interrupt is held off while they run.
+pub struct SharedP10<D> {
+    driver: Mutex<RefCell<Option<D>>>,
+}
+
+impl<D> Default for SharedP10<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D> SharedP10<D> {
+    /// Empty slot, see [`init`](Self::init).
+    pub const fn new() -> Self {
+        Self {
+            driver: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Move `driver` into the slot, returning the one it replaces.
+    pub fn init(&self, driver: D) -> Option<D> {
+        critical_section::with(|cs| self.driver.borrow_ref_mut(cs).replace(driver))
+    }
+
+    /// Move the driver out of the slot.
+    pub fn take(&self) -> Option<D> {
+        critical_section::with(|cs| self.driver.borrow_ref_mut(cs).take())
+    }
+
+    /// Run `f` on the driver inside a critical section, `None` if the slot is empty.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut D) -> R) -> Option<R> {
+        critical_section::with(|cs| self.driver.borrow_ref_mut(cs).as_mut().map(f))
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > SharedP10<P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>>
+{
+    /// Scan the framebuffer once, for the refresh interrupt. Returns `false` without touching
+    /// the bus while the slot is empty.
+    pub fn refresh(&self) -> Result<bool, Error> {
+        self.lock(|driver| driver.update())
+            .transpose()
+            .map(|done| done.is_some())
+    }
+
+    /// Output one scan row, for a timer interrupt firing once per scan row, see
+    /// [`P10Led::tick`].
+    pub fn tick(&self) -> Result<bool, Error> {
+        self.lock(|driver| driver.tick())
+            .transpose()
+            .map(|done| done.is_some())
+    }
+
+    /// Like [`refresh`](Self::refresh), but re-sends the retained scan data, see
+    /// [`P10Led::rescan`].
+    pub fn rescan(&self) -> Result<bool, Error> {
+        self.lock(|driver| driver.rescan())
+            .transpose()
+            .map(|done| done.is_some())
+    }
+}
@@ -0,0 +1,149 @@
+//! Driver split into a drawing half and a scanning half.
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+use embedded_hal::{digital::OutputPin, spi::SpiBus};
+
+use crate::{
+    Blocking, BufferStorage, Buffers, EnableLine, Error, Frame, FrameChannel, P10Led, Panel32x16,
+    PanelGeometry, Receiver, RowSelector, Sender,
+};
+
+/// Drawing half of a split driver, owned by the rendering task.
+///
+/// Drawing goes to a private [`Frame`] which [`present`](Self::present) hands to the
+/// [`Scanner`], so the scan never shows a half-drawn image.
+pub struct Canvas<'a, const PX: usize, const PY: usize, G = Panel32x16> {
+    frame: Frame<PX, PY, G>,
+    tx: Sender<'a, PX, PY, G>,
+}
+
+impl<const PX: usize, const PY: usize, G> Canvas<'_, PX, PY, G> {
+    pub fn frame(&self) -> &Frame<PX, PY, G> {
+        &self.frame
+    }
+
+    pub fn frame_mut(&mut self) -> &mut Frame<PX, PY, G> {
+        &mut self.frame
+    }
+
+    /// Queue the drawn frame for display. Returns `false` if the scanner has not picked up
+    /// the previous one yet, the frame is then dropped and can be presented again.
+    pub fn present(&mut self) -> bool {
+        self.tx.try_send(&self.frame)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> DrawTarget for Canvas<'_, PX, PY, G> {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.frame.draw_iter(pixels)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const PX: usize, const PY: usize, G: PanelGeometry> OriginDimensions
+    for Canvas<'_, PX, PY, G>
+{
+    fn size(&self) -> Size {
+        self.frame.size()
+    }
+}
+
+/// Scanning half of a split driver, owning the bus and pins, e.g. moved into the refresh
+/// interrupt.
+pub struct Scanner<
+    'a,
+    SPI,
+    E: EnableLine,
+    R: RowSelector,
+    L: OutputPin,
+    const PX: usize,
+    const PY: usize,
+    G: PanelGeometry = Panel32x16,
+    BUF: BufferStorage<PX, PY> = Buffers<PX, PY>,
+> {
+    driver: P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>,
+    rx: Receiver<'a, PX, PY, G>,
+}
+
+impl<
+        'a,
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > Scanner<'a, SPI, E, R, L, PX, PY, G, BUF>
+{
+    /// Load the frame presented last, if any, and refresh once. Returns whether a new frame
+    /// was loaded.
+    pub fn refresh(&mut self) -> Result<bool, Error> {
+        let received = self.driver.receive_frame(&mut self.rx);
+        self.driver.update()?;
+        Ok(received)
+    }
+
+    /// Output one scan row, see [`P10Led::tick`]. Presented frames are only taken over at
+    /// the start of a refresh so rows from two frames are not mixed.
+    pub fn tick(&mut self) -> Result<(), Error> {
+        if self.driver.scan_phase() == 0 {
+            self.driver.receive_frame(&mut self.rx);
+        }
+        self.driver.tick()
+    }
+
+    /// The driver, e.g. to change its settings from the refresh side.
+    pub fn driver(&mut self) -> &mut P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF> {
+        &mut self.driver
+    }
+
+    /// Take the driver back, the channel is freed along with the [`Canvas`].
+    pub fn into_inner(self) -> P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF> {
+        self.driver
+    }
+}
+
+impl<
+        SPI: SpiBus,
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        const PX: usize,
+        const PY: usize,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    > P10Led<SPI, E, R, L, PX, PY, Blocking, G, BUF>
+{
+    /// Split the driver into a [`Canvas`] for the rendering task and a [`Scanner`] for the
+    /// refresh side, handing frames over through `channel`.
+    ///
+    /// The canvas starts with the current framebuffer content.
+    #[allow(clippy::type_complexity)]
+    pub fn split<'a>(
+        self,
+        channel: &'a mut FrameChannel<PX, PY, G>,
+    ) -> (
+        Canvas<'a, PX, PY, G>,
+        Scanner<'a, SPI, E, R, L, PX, PY, G, BUF>,
+    ) {
+        let mut frame = Frame::new();
+        self.store_frame(&mut frame);
+        let (tx, rx) = channel.split();
+        (Canvas { frame, tx }, Scanner { driver: self, rx })
+    }
+}
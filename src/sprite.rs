@@ -0,0 +1,92 @@
+//! 1 bit per pixel images and movable sprites.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable, Pixel,
+};
+
+/// Packed 1 bit per pixel image, rows padded to whole bytes with the leftmost pixel in bit 7.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bitmap<'a> {
+    pub data: &'a [u8],
+    pub width: u32,
+    pub height: u32,
+}
+
+impl<'a> Bitmap<'a> {
+    pub const fn new(data: &'a [u8], width: u32, height: u32) -> Self {
+        Self {
+            data,
+            width,
+            height,
+        }
+    }
+
+    pub const fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// State of the pixel at `x`, `y`, `false` outside the image.
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let stride = self.width.div_ceil(8);
+        let byte = self.data.get((y * stride + x / 8) as usize).copied();
+        byte.unwrap_or(0) & (0x80 >> (x % 8)) != 0
+    }
+}
+
+/// A [`Bitmap`] at a position on the display that can be moved around.
+///
+/// Drawing sets lit and clears unlit image pixels, so a sprite overwrites what is below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sprite<'a> {
+    pub bitmap: Bitmap<'a>,
+    pub position: Point,
+    pub color: BinaryColor,
+}
+
+impl<'a> Sprite<'a> {
+    pub const fn new(bitmap: Bitmap<'a>, position: Point) -> Self {
+        Self {
+            bitmap,
+            position,
+            color: BinaryColor::On,
+        }
+    }
+
+    pub fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(self.position, self.bitmap.size())
+    }
+
+    /// Clear the area covered by the sprite.
+    pub fn erase<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        target.fill_solid(&self.bounding_box(), self.color.invert())
+    }
+}
+
+impl Drawable for Sprite<'_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let bitmap = self.bitmap;
+        let (on, off) = (self.color, self.color.invert());
+        target.draw_iter((0..bitmap.height).flat_map(|y| {
+            (0..bitmap.width).map(move |x| {
+                let pos = self.position + Point::new(x as i32, y as i32);
+                Pixel(pos, if bitmap.pixel(x, y) { on } else { off })
+            })
+        }))
+    }
+}
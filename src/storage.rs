@@ -0,0 +1,77 @@
+//! Frames and animations streamed from files, e.g. on an SD card through `embedded-sdmmc`.
+//!
+//! A file holds one or more packed frames back to back, each in the layout of
+//! [`Frame::as_bytes`], so content can be prepared on a PC and updated by swapping the card.
+
+use embedded_io::{Read, ReadExactError, Seek};
+
+use crate::{Frame, PanelGeometry};
+
+/// Fill `frame` from the next packed frame of `reader`.
+///
+/// Returns `false` at the end of the data; a truncated trailing frame is treated as the end.
+pub fn read_frame<R: Read, const PX: usize, const PY: usize, G: PanelGeometry>(
+    reader: &mut R,
+    frame: &mut Frame<PX, PY, G>,
+) -> Result<bool, R::Error> {
+    match reader.read_exact(frame.as_bytes_mut()) {
+        Ok(()) => Ok(true),
+        Err(ReadExactError::UnexpectedEof) => Ok(false),
+        Err(ReadExactError::Other(e)) => Err(e),
+    }
+}
+
+/// Sequence of frames played from a file.
+pub struct Animation<R> {
+    reader: R,
+    frames: u32,
+}
+
+impl<R: Read> Animation<R> {
+    /// Play the frames of `reader`.
+    pub fn new(reader: R) -> Self {
+        Self { reader, frames: 0 }
+    }
+
+    /// Frames read since the start of the current pass.
+    pub fn position(&self) -> u32 {
+        self.frames
+    }
+
+    /// Fill `frame` with the next frame. Returns `false` once the animation has ended.
+    pub fn next_frame<const PX: usize, const PY: usize, G: PanelGeometry>(
+        &mut self,
+        frame: &mut Frame<PX, PY, G>,
+    ) -> Result<bool, R::Error> {
+        let read = read_frame(&mut self.reader, frame)?;
+        self.frames += read as u32;
+        Ok(read)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> Animation<R> {
+    pub fn rewind(&mut self) -> Result<(), R::Error> {
+        self.frames = 0;
+        self.reader.rewind().map(drop)
+    }
+
+    /// Like [`next_frame`](Self::next_frame), but starts over from the first frame at the end.
+    /// Returns `false` only for an empty file.
+    pub fn next_frame_looped<const PX: usize, const PY: usize, G: PanelGeometry>(
+        &mut self,
+        frame: &mut Frame<PX, PY, G>,
+    ) -> Result<bool, R::Error> {
+        if self.next_frame(frame)? {
+            return Ok(true);
+        }
+        if self.frames == 0 {
+            return Ok(false);
+        }
+        self.rewind()?;
+        self.next_frame(frame)
+    }
+}
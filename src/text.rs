@@ -0,0 +1,562 @@
+//! Text rendering onto any binary [`DrawTarget`].
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable, Pixel,
+};
+
+use crate::{font, font::Font, Clipped};
+
+/// Legibility effect drawn in the background color around the glyphs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextEffect {
+    #[default]
+    None,
+    /// 1 pixel outline around every glyph, i.e. the glyphs dilated by one pixel.
+    Outline,
+    /// Copy of the glyphs offset by `dx`, `dy`.
+    Shadow { dx: i8, dy: i8 },
+}
+
+/// Horizontal placement of text relative to its position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HorizontalAlignment {
+    /// Position is the left edge.
+    #[default]
+    Left,
+    /// Position is the horizontal center.
+    Center,
+    /// Position is the right edge.
+    Right,
+}
+
+/// Vertical placement of text relative to its position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAlignment {
+    /// Position is the top glyph row.
+    #[default]
+    Top,
+    /// Position is the vertical center.
+    Middle,
+    /// Position is the bottom glyph row.
+    Bottom,
+}
+
+/// Appearance of rendered text.
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle<'f> {
+    pub font: &'f Font,
+    pub color: BinaryColor,
+    pub effect: TextEffect,
+    /// Draw every glyph column twice, for headlines readable at distance.
+    pub double_width: bool,
+    /// OR every glyph with a copy shifted one pixel right for pseudo-bold strokes.
+    pub bold: bool,
+    pub horizontal: HorizontalAlignment,
+    pub vertical: VerticalAlignment,
+}
+
+impl<'f> TextStyle<'f> {
+    pub const fn new(font: &'f Font) -> Self {
+        Self {
+            font,
+            color: BinaryColor::On,
+            effect: TextEffect::None,
+            double_width: false,
+            bold: false,
+            horizontal: HorizontalAlignment::Left,
+            vertical: VerticalAlignment::Top,
+        }
+    }
+
+    pub const fn with_effect(mut self, effect: TextEffect) -> Self {
+        self.effect = effect;
+        self
+    }
+
+    pub const fn with_double_width(mut self, double_width: bool) -> Self {
+        self.double_width = double_width;
+        self
+    }
+
+    pub const fn with_bold(mut self, bold: bool) -> Self {
+        self.bold = bold;
+        self
+    }
+
+    pub const fn with_alignment(
+        mut self,
+        horizontal: HorizontalAlignment,
+        vertical: VerticalAlignment,
+    ) -> Self {
+        self.horizontal = horizontal;
+        self.vertical = vertical;
+        self
+    }
+
+    /// Top-left corner of `text` placed at `position` according to the alignment.
+    pub fn top_left(&self, text: &str, position: Point) -> Point {
+        let width = self.text_width(text) as i32;
+        let height = self.font.height as i32;
+        let x = match self.horizontal {
+            HorizontalAlignment::Left => position.x,
+            HorizontalAlignment::Center => position.x - width / 2,
+            HorizontalAlignment::Right => position.x - width + 1,
+        };
+        let y = match self.vertical {
+            VerticalAlignment::Top => position.y,
+            VerticalAlignment::Middle => position.y - height / 2,
+            VerticalAlignment::Bottom => position.y - height + 1,
+        };
+        Point::new(x, y)
+    }
+
+    /// Anchor point inside `area` matching the alignment, e.g. its center for centered text.
+    pub fn anchor(&self, area: &Rectangle) -> Point {
+        let Point { x, y } = area.top_left;
+        let Size { width, height } = area.size;
+        let (w, h) = (width as i32, height as i32);
+        Point::new(
+            match self.horizontal {
+                HorizontalAlignment::Left => x,
+                HorizontalAlignment::Center => x + w / 2,
+                HorizontalAlignment::Right => x + w - 1,
+            },
+            match self.vertical {
+                VerticalAlignment::Top => y,
+                VerticalAlignment::Middle => y + h / 2,
+                VerticalAlignment::Bottom => y + h - 1,
+            },
+        )
+    }
+
+    fn scale_x(&self) -> u32 {
+        if self.double_width {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Width of a rendered glyph in pixels.
+    pub fn glyph_width(&self) -> u32 {
+        self.font.width as u32 * self.scale_x() + self.bold as u32
+    }
+
+    /// Horizontal distance between the origins of two neighbouring glyphs.
+    pub fn advance(&self) -> u32 {
+        self.glyph_width() + self.font.spacing as u32
+    }
+
+    /// Width of `text` in pixels, without trailing spacing.
+    pub fn text_width(&self, text: &str) -> u32 {
+        match text.chars().count() as u32 {
+            0 => 0,
+            n => n * self.advance() - self.font.spacing as u32,
+        }
+    }
+
+    /// Positions of the lit glyph pixels of `text` with its top-left corner at `origin`.
+    fn glyph_pixels<'a>(
+        &'a self,
+        text: &'a str,
+        origin: Point,
+        wave: Option<Wave>,
+    ) -> impl Iterator<Item = Point> + 'a {
+        let font = self.font;
+        let advance = self.advance();
+        let scale = self.scale_x() as i32;
+        let repeat = scale + self.bold as i32;
+        text.chars().enumerate().flat_map(move |(i, c)| {
+            let x0 = origin.x + (i as u32 * advance) as i32;
+            let y0 = origin.y + wave.map_or(0, |wave| wave.offset(i));
+            font.glyph(c)
+                .unwrap_or(&[])
+                .iter()
+                .enumerate()
+                .flat_map(move |(col, &bits)| {
+                    let x = x0 + col as i32 * scale;
+                    (0..font.height as i32)
+                        .filter(move |&row| bits & (1 << row) != 0)
+                        .flat_map(move |row| {
+                            (0..repeat).map(move |dx| Point::new(x + dx, y0 + row))
+                        })
+                })
+        })
+    }
+}
+
+/// Shape of a [`Wave`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WaveShape {
+    /// Characters swing up and down around their line.
+    #[default]
+    Sine,
+    /// Characters hop up from their line and fall back.
+    Bounce,
+}
+
+/// Per-character vertical offset animation, advanced by changing `phase` every frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wave {
+    pub shape: WaveShape,
+    /// Peak offset in pixels.
+    pub amplitude: u8,
+    /// Characters per wave period.
+    pub wavelength: u8,
+    /// Position within the period, a full turn is 256.
+    pub phase: u8,
+}
+
+/// Quarter period of a sine scaled to 127.
+const SINE: [u8; 17] = [
+    0, 12, 25, 37, 49, 60, 71, 81, 90, 98, 106, 112, 117, 122, 125, 126, 127,
+];
+
+impl Wave {
+    /// Vertical offset of character `index`, negative is up.
+    pub fn offset(&self, index: usize) -> i32 {
+        let step = 256 / self.wavelength.max(1) as usize;
+        let angle = (self.phase as usize + index * step) % 256;
+        let quarter = angle % 64 / 4;
+        let sine = match angle / 64 {
+            0 => SINE[quarter] as i32,
+            1 => SINE[16 - quarter] as i32,
+            2 => -(SINE[quarter] as i32),
+            _ => -(SINE[16 - quarter] as i32),
+        };
+        let sine = match self.shape {
+            WaveShape::Sine => sine,
+            WaveShape::Bounce => sine.abs(),
+        };
+        -(sine * self.amplitude as i32 / 127)
+    }
+}
+
+/// A string drawn at `position`, placed according to the style alignment.
+#[derive(Clone, Copy, Debug)]
+pub struct Text<'a, 'f> {
+    pub text: &'a str,
+    pub position: Point,
+    pub style: TextStyle<'f>,
+    pub wave: Option<Wave>,
+}
+
+impl<'a, 'f> Text<'a, 'f> {
+    pub const fn new(text: &'a str, position: Point, style: TextStyle<'f>) -> Self {
+        Self {
+            text,
+            position,
+            style,
+            wave: None,
+        }
+    }
+
+    /// Animate the characters with `wave`, e.g. for attention-grabbing promotional signs.
+    pub const fn with_wave(mut self, wave: Wave) -> Self {
+        self.wave = Some(wave);
+        self
+    }
+
+    /// Text aligned inside `area` according to the style alignment, e.g. centered on a panel.
+    pub fn in_area(text: &'a str, area: &Rectangle, style: TextStyle<'f>) -> Self {
+        Self::new(text, style.anchor(area), style)
+    }
+}
+
+impl Drawable for Text<'_, '_> {
+    type Color = BinaryColor;
+    /// Position right after the text, for drawing a continuation.
+    type Output = Point;
+
+    fn draw<D>(&self, target: &mut D) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let style = &self.style;
+        let background = style.color.invert();
+        let origin = style.top_left(self.text, self.position);
+        let pixels = style.glyph_pixels(self.text, origin, self.wave);
+        match style.effect {
+            TextEffect::None => {}
+            TextEffect::Outline => target.draw_iter(pixels.flat_map(|p| {
+                (-1..=1).flat_map(move |dy| {
+                    (-1..=1).map(move |dx| Pixel(p + Point::new(dx, dy), background))
+                })
+            }))?,
+            TextEffect::Shadow { dx, dy } => {
+                let offset = Point::new(dx as i32, dy as i32);
+                target.draw_iter(pixels.map(|p| Pixel(p + offset, background)))?
+            }
+        }
+        target.draw_iter(
+            style
+                .glyph_pixels(self.text, origin, self.wave)
+                .map(|p| Pixel(p, style.color)),
+        )?;
+
+        let width = style.text_width(self.text) + style.font.spacing as u32;
+        Ok(Point::new(origin.x + width as i32, self.position.y))
+    }
+}
+
+/// Two text lines stacked within `area`, e.g. a 16 pixel high panel with [`TINY_3X5`].
+///
+/// The free height is split evenly above, between and below the lines. Each line is drawn
+/// clipped to its own band and can be scrolled independently.
+///
+/// [`TINY_3X5`]: crate::font::TINY_3X5
+#[derive(Clone, Copy, Debug)]
+pub struct TwoLineLayout<'f> {
+    pub area: Rectangle,
+    pub style: TextStyle<'f>,
+}
+
+impl<'f> TwoLineLayout<'f> {
+    pub const fn new(area: Rectangle, style: TextStyle<'f>) -> Self {
+        Self { area, style }
+    }
+
+    fn gap(&self) -> i32 {
+        (self.area.size.height as i32 - 2 * self.style.font.height as i32).max(0) / 3
+    }
+
+    /// Top of the glyphs of `line` (`0` or `1`).
+    pub fn line_top(&self, line: usize) -> i32 {
+        let gap = self.gap();
+        self.area.top_left.y + gap + line.min(1) as i32 * (gap + self.style.font.height as i32)
+    }
+
+    /// Baseline, i.e. bottom glyph row, of `line`.
+    pub fn baseline(&self, line: usize) -> i32 {
+        self.line_top(line) + self.style.font.height as i32 - 1
+    }
+
+    /// Band of `line` the text is clipped to.
+    pub fn line_area(&self, line: usize) -> Rectangle {
+        Rectangle::new(
+            Point::new(self.area.top_left.x, self.line_top(line)),
+            Size::new(self.area.size.width, self.style.font.height as u32),
+        )
+    }
+
+    /// Scroll offset for a marquee at `step`: the text enters from the right edge and
+    /// restarts once it has left on the left.
+    pub fn marquee_offset(&self, text: &str, step: u32) -> i32 {
+        let period = self.style.text_width(text) + self.area.size.width;
+        (step % period) as i32 - self.area.size.width as i32
+    }
+
+    /// Clear the band of `line` and draw `text` aligned in it and shifted left by `offset` pixels.
+    pub fn draw_line<D>(
+        &self,
+        target: &mut D,
+        line: usize,
+        text: &str,
+        offset: i32,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let band = self.line_area(line);
+        let mut target = Clipped::new(target, band);
+        target.fill_solid(&band, self.style.color.invert())?;
+        let position = self.style.anchor(&band) - Point::new(offset, 0);
+        Text::new(text, position, self.style).draw(&mut target)?;
+        Ok(())
+    }
+}
+
+/// Fixed-capacity string for formatting text without allocation.
+///
+/// Output beyond `N` bytes is truncated at a character boundary.
+#[derive(Clone)]
+pub struct FmtBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for FmtBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FmtBuf<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> core::fmt::Write for FmtBuf<N> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let mut n = s.len().min(N - self.len);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+/// Format `args` into a stack buffer and draw it at `position` with the default font.
+///
+/// Backs [`p10_print!`](crate::p10_print), at most 64 bytes are shown.
+pub fn print<D>(
+    target: &mut D,
+    position: Point,
+    args: core::fmt::Arguments<'_>,
+) -> Result<Point, D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let mut buf = FmtBuf::<64>::new();
+    let _ = core::fmt::Write::write_fmt(&mut buf, args);
+    Text::new(buf.as_str(), position, TextStyle::new(&font::DEFAULT)).draw(target)
+}
+
+/// Format and draw text with the default font in one line, e.g. for status displays.
+///
+/// ```ignore
+/// p10_print!(&mut display, Point::new(0, 4), "T={}C", temperature)?;
+/// ```
+#[macro_export]
+macro_rules! p10_print {
+    ($target:expr, $position:expr, $($arg:tt)*) => {
+        $crate::text::print($target, $position, core::format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{font::TINY_3X5, Frame};
+
+    /// Lit pixels of `frame`, row by row.
+    fn lit(frame: &Frame) -> std::vec::Vec<(usize, usize)> {
+        (0..Frame::<1, 1>::HEIGHT)
+            .flat_map(|y| (0..Frame::<1, 1>::WIDTH).map(move |x| (x, y)))
+            .filter(|&(x, y)| frame.get(x, y))
+            .collect()
+    }
+
+    #[test]
+    fn glyphs_are_drawn_column_by_column() {
+        let mut frame = Frame::new();
+        let style = TextStyle::new(&TINY_3X5);
+        let Ok(next) = Text::new("1.", Point::new(2, 3), style).draw(&mut frame);
+        // '1' is 0x12, 0x1f, 0x10 and '.' lights the middle of its bottom row.
+        let want = [
+            (3, 3),
+            (2, 4),
+            (3, 4),
+            (3, 5),
+            (3, 6),
+            (2, 7),
+            (3, 7),
+            (4, 7),
+            (7, 7),
+        ];
+        assert_eq!(lit(&frame), want);
+        assert_eq!(next, Point::new(2 + 8, 3));
+    }
+
+    #[test]
+    fn alignment_moves_the_top_left_corner() {
+        let style = TextStyle::new(&TINY_3X5);
+        let position = Point::new(16, 8);
+        // "123" is 11 pixels wide and 5 high.
+        let cases = [
+            (
+                HorizontalAlignment::Left,
+                VerticalAlignment::Top,
+                Point::new(16, 8),
+            ),
+            (
+                HorizontalAlignment::Center,
+                VerticalAlignment::Middle,
+                Point::new(11, 6),
+            ),
+            (
+                HorizontalAlignment::Right,
+                VerticalAlignment::Bottom,
+                Point::new(6, 4),
+            ),
+        ];
+        for (horizontal, vertical, top_left) in cases {
+            let style = style.with_alignment(horizontal, vertical);
+            assert_eq!(style.top_left("123", position), top_left);
+        }
+
+        let area = Rectangle::new(Point::new(0, 0), Size::new(32, 16));
+        let centered = style.with_alignment(HorizontalAlignment::Center, VerticalAlignment::Middle);
+        assert_eq!(centered.anchor(&area), Point::new(16, 8));
+    }
+
+    #[test]
+    fn double_width_and_bold_widen_the_glyphs() {
+        let style = TextStyle::new(&TINY_3X5).with_double_width(true);
+        assert_eq!((style.glyph_width(), style.text_width("12")), (6, 13));
+        let style = TextStyle::new(&TINY_3X5).with_bold(true);
+        assert_eq!((style.glyph_width(), style.text_width("12")), (4, 9));
+    }
+
+    #[test]
+    fn two_lines_share_the_free_height() {
+        let area = Rectangle::new(Point::new(0, 0), Size::new(32, 16));
+        let layout = TwoLineLayout::new(area, TextStyle::new(&TINY_3X5));
+        // Gaps of 2 pixels above, between and below the 5 pixel lines.
+        assert_eq!((layout.line_top(0), layout.baseline(0)), (2, 6));
+        assert_eq!((layout.line_top(1), layout.baseline(1)), (9, 13));
+        assert_eq!(layout.marquee_offset("1", 0), -32);
+        assert_eq!(layout.marquee_offset("1", 35), -32);
+    }
+
+    #[test]
+    fn lines_are_clipped_to_their_band() {
+        let mut frame = Frame::new();
+        let area = Rectangle::new(Point::new(0, 0), Size::new(32, 16));
+        let layout = TwoLineLayout::new(area, TextStyle::new(&TINY_3X5));
+        frame.set(0, 0, true);
+        let Ok(()) = layout.draw_line(&mut frame, 1, "8", 0);
+        let lit = lit(&frame);
+        assert!(lit.contains(&(0, 0)));
+        assert!(lit[1..].iter().all(|&(_, y)| (9..14).contains(&y)));
+    }
+
+    #[test]
+    fn clipped_bounds_stay_inside_the_target() {
+        use embedded_graphics_core::geometry::Dimensions;
+
+        let mut frame = Frame::<1, 1>::new();
+        let band = Rectangle::new(Point::new(24, 12), Size::new(16, 8));
+        let clipped = Clipped::new(&mut frame, band);
+        let inside = Rectangle::new(Point::new(24, 12), Size::new(8, 4));
+        assert_eq!(clipped.bounding_box(), inside);
+    }
+
+    #[test]
+    fn format_buffer_truncates_at_a_char_boundary() {
+        let mut buf = FmtBuf::<4>::new();
+        let _ = core::fmt::Write::write_str(&mut buf, "abcé");
+        assert_eq!(buf.as_str(), "abc");
+        buf.clear();
+        let _ = core::fmt::Write::write_str(&mut buf, "aé");
+        assert_eq!(buf.as_str(), "aé");
+    }
+}
@@ -0,0 +1,131 @@
+//! Time source used by pacing and rate limiting.
+
+/// Monotonic microsecond clock provided by the application, e.g. a free-running timer.
+///
+/// The counter is allowed to wrap, intervals are computed with wrapping arithmetic.
+pub trait Clock {
+    fn now_us(&self) -> u32;
+}
+
+impl<F: Fn() -> u32> Clock for F {
+    fn now_us(&self) -> u32 {
+        self()
+    }
+}
+
+/// Time left until `deadline` of `clock`, `None` once it has passed.
+pub(crate) fn remaining_us(clock: &impl Clock, deadline: u32) -> Option<u32> {
+    let remaining = deadline.wrapping_sub(clock.now_us());
+    (remaining as i32 > 0).then_some(remaining)
+}
+
+/// Refresh duty reduction for static content, see `P10Led::update_adaptive`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveRefresh {
+    /// Time without drawing after which the idle gaps start, in microseconds.
+    pub idle_after_us: u32,
+    /// Blanked gap inserted after each refresh while idle, in microseconds.
+    pub idle_gap_us: u32,
+}
+
+/// Scan timing for displays that are filmed, see `P10Led::update_camera`.
+///
+/// Cameras sample each line of the image at a slightly different time, so a slow scan with
+/// blanked gaps shows up as rolling dark bands. Short row holds repeated back to back push
+/// the scan frequency well above common shutter speeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CameraRefresh {
+    /// Time each row stays enabled, in microseconds.
+    pub row_hold_us: u32,
+    /// Full scans done per call before the output is blanked.
+    pub passes: u16,
+}
+
+impl Default for CameraRefresh {
+    /// About 2.5 kHz scan frequency for roughly 20 ms per call.
+    fn default() -> Self {
+        Self {
+            row_hold_us: 100,
+            passes: 50,
+        }
+    }
+}
+
+/// Software dimming for enable lines on a plain GPIO, see `P10Led::update_dimmed`.
+///
+/// Each row is lit for `on_us` and then blanked for `off_us`, so the brightness is roughly
+/// `on_us / (on_us + off_us)`. The steps are as coarse as the delay provider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftDimming {
+    /// Time each row stays enabled, in microseconds.
+    pub on_us: u32,
+    /// Time the output stays disabled after each row, in microseconds.
+    pub off_us: u32,
+}
+
+impl SoftDimming {
+    /// Split a row period of `row_us` according to `level`, `0` is off and `255` full
+    /// brightness.
+    pub fn from_level(level: u8, row_us: u32) -> Self {
+        let on_us = (row_us as u64 * level as u64 / u8::MAX as u64) as u32;
+        Self {
+            on_us,
+            off_us: row_us - on_us,
+        }
+    }
+}
+
+impl Default for SoftDimming {
+    /// Full brightness with a 200 us row period.
+    fn default() -> Self {
+        Self::from_level(u8::MAX, 200)
+    }
+}
+
+/// Preset trading refresh rate against CPU time, see `P10Led::set_profile`.
+///
+/// Each profile sets the row hold, the flush interval, the idle gaps and whether the scan
+/// data is rebuilt between flushes together, instead of tuning those separately.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Rebuild and scan on every call as fast as the bus allows. The default.
+    #[default]
+    MaxRefresh,
+    /// Full rate while drawing, with short idle gaps once the content is static.
+    Balanced,
+    /// Held rows, rebuilds at most every 20 ms with the retained scan data re-sent in
+    /// between, and long idle gaps for static content.
+    MinCpu,
+}
+
+impl Profile {
+    /// Time each row stays enabled, in microseconds.
+    pub fn row_hold_us(self) -> u32 {
+        match self {
+            Profile::MaxRefresh | Profile::Balanced => 0,
+            Profile::MinCpu => 500,
+        }
+    }
+
+    /// Minimum interval between rebuilds of the scan data, in microseconds.
+    pub fn min_flush_interval_us(self) -> u32 {
+        match self {
+            Profile::MaxRefresh | Profile::Balanced => 0,
+            Profile::MinCpu => 20_000,
+        }
+    }
+
+    pub fn adaptive_refresh(self) -> Option<AdaptiveRefresh> {
+        match self {
+            Profile::MaxRefresh => None,
+            Profile::Balanced => Some(AdaptiveRefresh {
+                idle_after_us: 1_000_000,
+                idle_gap_us: 2_000,
+            }),
+            Profile::MinCpu => Some(AdaptiveRefresh {
+                idle_after_us: 500_000,
+                idle_gap_us: 8_000,
+            }),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Transit destination sign layout.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable,
+};
+
+use crate::{
+    text::{HorizontalAlignment, Text, TextStyle, VerticalAlignment},
+    Clipped, Clock,
+};
+
+/// Text shown in the page block of a [`DestinationSign`] for `dwell_us` microseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Page<'a> {
+    pub text: &'a str,
+    pub dwell_us: u32,
+}
+
+/// Route number fixed on the left and pages of destination text alternating on the right,
+/// the new page sliding in from below, like the signs on buses and trams.
+///
+/// Call [`tick`](Self::tick) once per frame and redraw while it returns `true`.
+#[derive(Clone, Copy, Debug)]
+pub struct DestinationSign<'a, 'f> {
+    area: Rectangle,
+    route: &'a str,
+    route_style: TextStyle<'f>,
+    route_width: u32,
+    pages: &'a [Page<'a>],
+    style: TextStyle<'f>,
+    slide_us: u32,
+    page: usize,
+    page_start_us: Option<u32>,
+    /// Pixels the next page has slid in so far, `0` while a page dwells.
+    slid: u32,
+}
+
+impl<'a, 'f> DestinationSign<'a, 'f> {
+    /// Sign filling `area`, with the route in a block as wide as the route text and the
+    /// pages drawn with `style` centered in the rest.
+    pub fn new(
+        area: Rectangle,
+        route: &'a str,
+        pages: &'a [Page<'a>],
+        style: TextStyle<'f>,
+    ) -> Self {
+        let style = style.with_alignment(HorizontalAlignment::Center, VerticalAlignment::Middle);
+        Self {
+            area,
+            route,
+            route_style: style,
+            route_width: style.text_width(route) + 2,
+            pages,
+            style,
+            slide_us: 300_000,
+            page: 0,
+            page_start_us: None,
+            slid: 0,
+        }
+    }
+
+    /// Draw the route with `style`, e.g. a bold or double-width one.
+    pub fn with_route_style(mut self, style: TextStyle<'f>) -> Self {
+        self.route_style =
+            style.with_alignment(HorizontalAlignment::Center, VerticalAlignment::Middle);
+        self.route_width = self.route_style.text_width(self.route) + 2;
+        self
+    }
+
+    /// Width of the route block in pixels.
+    pub fn with_route_width(mut self, width: u32) -> Self {
+        self.route_width = width;
+        self
+    }
+
+    /// Duration of the slide between pages, `0` switches instantly.
+    pub fn with_slide_us(mut self, slide_us: u32) -> Self {
+        self.slide_us = slide_us;
+        self
+    }
+
+    /// Index of the page currently shown, or sliding out.
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    fn route_area(&self) -> Rectangle {
+        let width = self.route_width.min(self.area.size.width);
+        Rectangle::new(self.area.top_left, Size::new(width, self.area.size.height))
+    }
+
+    fn page_area(&self) -> Rectangle {
+        let width = self.route_width.min(self.area.size.width);
+        Rectangle::new(
+            self.area.top_left + Point::new(width as i32, 0),
+            Size::new(self.area.size.width - width, self.area.size.height),
+        )
+    }
+
+    /// Advance the page timing to `clock`. Returns whether a redraw is needed.
+    pub fn tick(&mut self, clock: &impl Clock) -> bool {
+        let Some(page) = self.pages.get(self.page) else {
+            return false;
+        };
+        let now = clock.now_us();
+        let start = *self.page_start_us.get_or_insert(now);
+        let elapsed = now.wrapping_sub(start);
+        if elapsed < page.dwell_us || self.pages.len() == 1 {
+            return false;
+        }
+        let sliding = elapsed - page.dwell_us;
+        if sliding < self.slide_us {
+            let height = self.area.size.height as u64;
+            let slid = (sliding as u64 * height / self.slide_us as u64) as u32;
+            let changed = slid != self.slid;
+            self.slid = slid;
+            return changed;
+        }
+        self.page = (self.page + 1) % self.pages.len();
+        self.page_start_us = Some(start.wrapping_add(page.dwell_us + self.slide_us));
+        self.slid = 0;
+        true
+    }
+}
+
+impl Drawable for DestinationSign<'_, '_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let route = self.route_area();
+        target.fill_solid(&route, self.route_style.color.invert())?;
+        Text::in_area(self.route, &route, self.route_style).draw(target)?;
+
+        let area = self.page_area();
+        let mut target = Clipped::new(target, area);
+        target.fill_solid(&area, self.style.color.invert())?;
+        let Some(page) = self.pages.get(self.page) else {
+            return Ok(());
+        };
+        let anchor = self.style.anchor(&area) - Point::new(0, self.slid as i32);
+        Text::new(page.text, anchor, self.style).draw(&mut target)?;
+        if self.slid != 0 {
+            let next = &self.pages[(self.page + 1) % self.pages.len()];
+            let anchor = anchor + Point::new(0, area.size.height as i32);
+            Text::new(next.text, anchor, self.style).draw(&mut target)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+//! Navigable list menu.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable,
+};
+
+use crate::{
+    text::{Text, TextStyle},
+    Clipped,
+};
+
+/// Abstract navigation input, e.g. mapped from a couple of buttons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    Up,
+    Down,
+    Select,
+}
+
+/// List of items with an inverted selection bar, scrolling when it overflows its area.
+///
+/// Handy for configuring devices where the P10 panel and a few buttons are the only UI.
+#[derive(Clone, Copy, Debug)]
+pub struct Menu<'a, 'f> {
+    items: &'a [&'a str],
+    area: Rectangle,
+    style: TextStyle<'f>,
+    selected: usize,
+    top: usize,
+}
+
+impl<'a, 'f> Menu<'a, 'f> {
+    pub fn new(items: &'a [&'a str], area: Rectangle, style: TextStyle<'f>) -> Self {
+        Self {
+            items,
+            area,
+            style,
+            selected: 0,
+            top: 0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+        self.scroll_to_selection();
+    }
+
+    /// Height of one item including a blank row below it.
+    fn line_height(&self) -> u32 {
+        self.style.font.height as u32 + 1
+    }
+
+    fn visible_lines(&self) -> usize {
+        (self.area.size.height / self.line_height()).max(1) as usize
+    }
+
+    fn scroll_to_selection(&mut self) {
+        let lines = self.visible_lines();
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + lines {
+            self.top = self.selected + 1 - lines;
+        }
+    }
+
+    /// Apply an input. Moving wraps around at both ends. Returns the selected item index
+    /// on [`Input::Select`].
+    pub fn handle(&mut self, input: Input) -> Option<usize> {
+        let len = self.items.len();
+        if len == 0 {
+            return None;
+        }
+        match input {
+            Input::Up => self.selected = (self.selected + len - 1) % len,
+            Input::Down => self.selected = (self.selected + 1) % len,
+            Input::Select => return Some(self.selected),
+        }
+        self.scroll_to_selection();
+        None
+    }
+}
+
+impl Drawable for Menu<'_, '_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let mut target = Clipped::new(target, self.area);
+        let background = self.style.color.invert();
+        target.fill_solid(&self.area, background)?;
+
+        let line_height = self.line_height();
+        let visible = self.items.iter().enumerate().skip(self.top);
+        for (line, (index, item)) in visible.take(self.visible_lines()).enumerate() {
+            let band = Rectangle::new(
+                self.area.top_left + Point::new(0, (line as u32 * line_height) as i32),
+                Size::new(self.area.size.width, line_height),
+            );
+            let mut style = self.style;
+            if index == self.selected {
+                target.fill_solid(&band, self.style.color)?;
+                style.color = background;
+            }
+            Text::new(item, style.anchor(&band) + Point::new(1, 0), style).draw(&mut target)?;
+        }
+        Ok(())
+    }
+}
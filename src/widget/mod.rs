@@ -0,0 +1,10 @@
+//! Ready-made widgets drawn onto any binary draw target.
+
+pub mod destination;
+pub mod menu;
+pub mod odometer;
+pub mod screensaver;
+pub mod stopwatch;
+pub mod terminal;
+pub mod text_buffer;
+pub mod weather;
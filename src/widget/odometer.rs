@@ -0,0 +1,142 @@
+//! Rolling counter.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable,
+};
+
+use crate::{
+    text::{HorizontalAlignment, Text, TextStyle, VerticalAlignment},
+    Clipped,
+};
+
+const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+/// Fixed-width number whose changing digits roll vertically: the old digit moves up and
+/// out while the new one comes in from below, for counters and price displays.
+///
+/// Call [`tick`](Self::tick) once per frame and redraw while it returns `true`.
+#[derive(Clone, Copy, Debug)]
+pub struct Odometer<'f, const N: usize> {
+    position: Point,
+    style: TextStyle<'f>,
+    shown: [u8; N],
+    previous: [u8; N],
+    /// Pixels each digit has rolled so far, `0` when it is not rolling.
+    rolled: [u8; N],
+}
+
+impl<'f, const N: usize> Odometer<'f, N> {
+    /// Counter showing `value` with its top-left corner at `position`.
+    pub fn new(position: Point, style: TextStyle<'f>, value: u32) -> Self {
+        let digits = Self::digits(value);
+        Self {
+            position,
+            style,
+            shown: digits,
+            previous: digits,
+            rolled: [0; N],
+        }
+    }
+
+    /// Last `N` decimal digits of `value`, most significant first.
+    fn digits(mut value: u32) -> [u8; N] {
+        let mut digits = [0; N];
+        for digit in digits.iter_mut().rev() {
+            *digit = (value % 10) as u8;
+            value /= 10;
+        }
+        digits
+    }
+
+    /// Distance a digit travels while rolling.
+    fn travel(&self) -> u8 {
+        self.style.font.height + 1
+    }
+
+    /// Start rolling every digit that differs from `value`.
+    pub fn set(&mut self, value: u32) {
+        for (i, digit) in Self::digits(value).into_iter().enumerate() {
+            if digit != self.shown[i] {
+                // A digit changing again mid-roll restarts from what is visible most.
+                if self.rolled[i] * 2 >= self.travel() {
+                    self.previous[i] = self.shown[i];
+                }
+                self.shown[i] = digit;
+                self.rolled[i] = 1;
+            }
+        }
+    }
+
+    /// Whether any digit is still rolling.
+    pub fn is_rolling(&self) -> bool {
+        self.rolled.iter().any(|&r| r != 0)
+    }
+
+    /// Advance the rolls by one pixel. Returns whether a redraw is needed.
+    pub fn tick(&mut self) -> bool {
+        let travel = self.travel();
+        let mut changed = false;
+        for i in 0..N {
+            if self.rolled[i] != 0 {
+                self.rolled[i] += 1;
+                if self.rolled[i] >= travel {
+                    self.rolled[i] = 0;
+                    self.previous[i] = self.shown[i];
+                }
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Area covered by the counter.
+    pub fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            self.position,
+            Size::new(
+                (self.style.advance() * N as u32).saturating_sub(self.style.font.spacing as u32),
+                self.style.font.height as u32,
+            ),
+        )
+    }
+}
+
+impl<const N: usize> Drawable for Odometer<'_, N> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        // Digits are placed by cell, the alignment of the style does not apply.
+        let style = self
+            .style
+            .with_alignment(HorizontalAlignment::Left, VerticalAlignment::Top);
+        let advance = style.advance() as i32;
+        let height = style.font.height as u32;
+        for i in 0..N {
+            let cell = Rectangle::new(
+                self.position + Point::new(i as i32 * advance, 0),
+                Size::new(style.glyph_width(), height),
+            );
+            let mut target = Clipped::new(target, cell);
+            target.fill_solid(&cell, style.color.invert())?;
+            let rolled = self.rolled[i] as i32;
+            let top = cell.top_left;
+            if rolled == 0 {
+                Text::new(DIGITS[self.shown[i] as usize], top, style).draw(&mut target)?;
+            } else {
+                let old = top - Point::new(0, rolled);
+                let new = old + Point::new(0, self.travel() as i32);
+                Text::new(DIGITS[self.previous[i] as usize], old, style).draw(&mut target)?;
+                Text::new(DIGITS[self.shown[i] as usize], new, style).draw(&mut target)?;
+            }
+        }
+        Ok(())
+    }
+}
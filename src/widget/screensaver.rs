@@ -0,0 +1,172 @@
+//! Idle animations.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable,
+};
+
+use embedded_hal::digital::OutputPin;
+
+use crate::{
+    sprite::{Bitmap, Sprite},
+    BufferStorage, Clock, EnableLine, Error, P10Led, PanelGeometry, RowSelector, ScreenStack,
+};
+
+/// Screensaver bouncing a small image around the display, reversing at the edges.
+#[derive(Clone, Copy, Debug)]
+pub struct BouncingLogo<'a> {
+    sprite: Sprite<'a>,
+    bounds: Rectangle,
+    velocity: Point,
+}
+
+impl<'a> BouncingLogo<'a> {
+    /// Logo moving diagonally by one pixel per tick inside `bounds`.
+    pub fn new(logo: Bitmap<'a>, bounds: Rectangle) -> Self {
+        Self {
+            sprite: Sprite::new(logo, bounds.top_left),
+            bounds,
+            velocity: Point::new(1, 1),
+        }
+    }
+
+    /// Logo bouncing around the whole of `target`.
+    pub fn fullscreen(logo: Bitmap<'a>, target: &impl Dimensions) -> Self {
+        Self::new(logo, target.bounding_box())
+    }
+
+    pub fn with_velocity(mut self, velocity: Point) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn position(&self) -> Point {
+        self.sprite.position
+    }
+
+    fn step(&mut self) {
+        let min = self.bounds.top_left;
+        let size = self.bounds.size.saturating_sub(self.sprite.bitmap.size());
+        let max = min + Point::new(size.width as i32, size.height as i32);
+        let mut next = self.sprite.position + self.velocity;
+        if next.x < min.x || next.x > max.x {
+            self.velocity.x = -self.velocity.x;
+            next.x = next.x.clamp(min.x, max.x);
+        }
+        if next.y < min.y || next.y > max.y {
+            self.velocity.y = -self.velocity.y;
+            next.y = next.y.clamp(min.y, max.y);
+        }
+        self.sprite.position = next;
+    }
+
+    /// Erase the logo, move it by one step and draw it again.
+    pub fn tick<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.sprite.erase(target)?;
+        self.step();
+        self.sprite.draw(target)
+    }
+}
+
+/// Switches the display to a [`BouncingLogo`] and dims it after `timeout_us` without user
+/// drawing, and restores the previous content and brightness on the next user draw.
+///
+/// The content shown before is pushed onto the application's [`ScreenStack`] and popped on
+/// wake, so the screensaver nests with the other screens kept there. The display is dimmed
+/// with [`set_brightness`](P10Led::set_brightness), leaving the compensation alone.
+///
+/// Route user drawing through [`wake`](Self::wake) and call [`poll`](Self::poll) once per
+/// frame.
+pub struct IdleScreensaver<'a> {
+    saver: BouncingLogo<'a>,
+    timeout_us: u32,
+    dim: u8,
+    active: bool,
+    last_activity_us: Option<u32>,
+    saved_brightness: u8,
+}
+
+impl<'a> IdleScreensaver<'a> {
+    /// Run `saver` at a quarter brightness after `timeout_us` microseconds of inactivity.
+    pub fn new(saver: BouncingLogo<'a>, timeout_us: u32) -> Self {
+        Self {
+            saver,
+            timeout_us,
+            dim: u8::MAX / 4,
+            active: false,
+            last_activity_us: None,
+            saved_brightness: u8::MAX,
+        }
+    }
+
+    /// Brightness while the screensaver runs, `u8::MAX` for full brightness.
+    pub fn with_dim(mut self, level: u8) -> Self {
+        self.dim = level;
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Record user activity and hand out the display for drawing, popping the content and
+    /// restoring the brightness from before the screensaver if it was running.
+    #[allow(clippy::type_complexity)]
+    pub fn wake<'d, SPI, E, R, L, const PX: usize, const PY: usize, MODE, G, BUF, const N: usize>(
+        &mut self,
+        display: &'d mut P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>,
+        screens: &mut ScreenStack<N, PX, PY, G>,
+        clock: &impl Clock,
+    ) -> Result<&'d mut P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>, Error>
+    where
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    {
+        if self.active {
+            self.active = false;
+            screens.pop(display);
+            display.set_brightness(self.saved_brightness)?;
+        }
+        self.last_activity_us = Some(clock.now_us());
+        Ok(display)
+    }
+
+    /// Start the screensaver once the timeout has passed and advance it while it runs.
+    /// Returns whether it is active; it does not start while `screens` is full.
+    pub fn poll<SPI, E, R, L, const PX: usize, const PY: usize, MODE, G, BUF, const N: usize>(
+        &mut self,
+        display: &mut P10Led<SPI, E, R, L, PX, PY, MODE, G, BUF>,
+        screens: &mut ScreenStack<N, PX, PY, G>,
+        clock: &impl Clock,
+    ) -> Result<bool, Error>
+    where
+        E: EnableLine,
+        R: RowSelector,
+        L: OutputPin,
+        G: PanelGeometry,
+        BUF: BufferStorage<PX, PY>,
+    {
+        let now = clock.now_us();
+        if !self.active {
+            let last = *self.last_activity_us.get_or_insert(now);
+            if now.wrapping_sub(last) < self.timeout_us || !screens.push(display) {
+                return Ok(false);
+            }
+            self.active = true;
+            self.saved_brightness = display.target_brightness();
+            display.set_brightness(self.dim)?;
+            display.clear();
+        }
+        let Ok(()) = self.saver.tick(display);
+        Ok(true)
+    }
+}
@@ -0,0 +1,177 @@
+//! Stopwatch showing minutes, seconds and tenths.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable,
+};
+
+use crate::{
+    font::{CONDENSED_4X7, TINY_3X5},
+    text::{HorizontalAlignment, Text, TextStyle, VerticalAlignment},
+    Clock,
+};
+
+/// Characters of `MM:SS.T`.
+const CELLS: usize = 7;
+
+/// Stopwatch rendering `MM:SS.T` with lap capture, timed by a user [`Clock`].
+///
+/// [`draw_changed`](Self::draw_changed) redraws only the digits that changed since the
+/// previous call, so it can run every frame at little cost.
+#[derive(Clone, Copy, Debug)]
+pub struct Stopwatch<'f> {
+    position: Point,
+    style: TextStyle<'f>,
+    running: bool,
+    last_us: u32,
+    remainder_us: u32,
+    tenths: u32,
+    lap_start: u32,
+    shown: Option<[u8; CELLS]>,
+}
+
+impl<'f> Stopwatch<'f> {
+    /// Stopwatch with its top-left corner at `position` drawn with `style`.
+    pub fn new(position: Point, style: TextStyle<'f>) -> Self {
+        Self {
+            position,
+            style: style.with_alignment(HorizontalAlignment::Left, VerticalAlignment::Top),
+            running: false,
+            last_us: 0,
+            remainder_us: 0,
+            tenths: 0,
+            lap_start: 0,
+            shown: None,
+        }
+    }
+
+    /// Stopwatch with a built-in font picked to fill `width` pixels, sized for 1 to 3
+    /// panel widths.
+    pub fn for_width(position: Point, width: u32) -> Stopwatch<'static> {
+        let style = match width {
+            ..=63 => TextStyle::new(&TINY_3X5),
+            64..=95 => TextStyle::new(&CONDENSED_4X7),
+            _ => TextStyle::new(&CONDENSED_4X7).with_double_width(true),
+        };
+        Stopwatch::new(position, style)
+    }
+
+    pub fn start(&mut self, clock: &impl Clock) {
+        if !self.running {
+            self.last_us = clock.now_us();
+            self.running = true;
+        }
+    }
+
+    pub fn stop(&mut self, clock: &impl Clock) {
+        self.poll(clock);
+        self.running = false;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Stop and return to zero.
+    pub fn reset(&mut self) {
+        self.running = false;
+        self.remainder_us = 0;
+        self.tenths = 0;
+        self.lap_start = 0;
+    }
+
+    /// Fold the time passed since the last call into the elapsed time. Has to be called at
+    /// least once per clock wrap-around while running.
+    pub fn poll(&mut self, clock: &impl Clock) {
+        if !self.running {
+            return;
+        }
+        let now = clock.now_us();
+        self.remainder_us += now.wrapping_sub(self.last_us);
+        self.last_us = now;
+        self.tenths = self.tenths.wrapping_add(self.remainder_us / 100_000);
+        self.remainder_us %= 100_000;
+    }
+
+    /// Elapsed time in tenths of a second.
+    pub fn elapsed_tenths(&mut self, clock: &impl Clock) -> u32 {
+        self.poll(clock);
+        self.tenths
+    }
+
+    /// Capture a lap. Returns its duration in tenths of a second.
+    pub fn lap(&mut self, clock: &impl Clock) -> u32 {
+        let now = self.elapsed_tenths(clock);
+        let lap = now.wrapping_sub(self.lap_start);
+        self.lap_start = now;
+        lap
+    }
+
+    fn chars(&self) -> [u8; CELLS] {
+        let tenths = self.tenths % (100 * 60 * 10);
+        let (minutes, seconds) = (tenths / 600, tenths / 10 % 60);
+        let digit = |value: u32| b'0' + value as u8;
+        [
+            digit(minutes / 10),
+            digit(minutes % 10),
+            b':',
+            digit(seconds / 10),
+            digit(seconds % 10),
+            b'.',
+            digit(tenths % 10),
+        ]
+    }
+
+    /// Area covered by the stopwatch.
+    pub fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            self.position,
+            Size::new(
+                self.style.text_width("00:00.0"),
+                self.style.font.height as u32,
+            ),
+        )
+    }
+
+    fn draw_cells<D>(&mut self, target: &mut D, all: bool) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let chars = self.chars();
+        let advance = self.style.advance() as i32;
+        for (i, c) in chars.iter().enumerate() {
+            if !all && self.shown.is_some_and(|shown| shown[i] == *c) {
+                continue;
+            }
+            let cell = Rectangle::new(
+                self.position + Point::new(i as i32 * advance, 0),
+                Size::new(self.style.glyph_width(), self.style.font.height as u32),
+            );
+            target.fill_solid(&cell, self.style.color.invert())?;
+            let text = core::str::from_utf8(core::slice::from_ref(c)).unwrap_or("");
+            Text::new(text, cell.top_left, self.style).draw(target)?;
+        }
+        self.shown = Some(chars);
+        Ok(())
+    }
+
+    /// Bring the display up to date, redrawing only the characters that changed.
+    pub fn draw_changed<D>(&mut self, target: &mut D, clock: &impl Clock) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.poll(clock);
+        self.draw_cells(target, false)
+    }
+
+    /// Redraw all characters, e.g. after the area was overwritten.
+    pub fn redraw<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.draw_cells(target, true)
+    }
+}
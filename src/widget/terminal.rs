@@ -0,0 +1,132 @@
+//! Scrolling text region for boot logs and diagnostics.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget, geometry::Point, pixelcolor::BinaryColor, primitives::Rectangle,
+};
+
+use super::text_buffer::TextBuffer;
+use crate::text::TextStyle;
+
+/// Character shown at the cursor position.
+const CURSOR: u8 = b'_';
+
+/// Terminal-like region of `LINES` by `COLS` characters.
+///
+/// Text is appended through [`core::fmt::Write`], so `write!` works directly. Long lines
+/// wrap, `'\n'` starts a new line and `'\r'` returns to its start; once the last line is
+/// full, the content scrolls up by a line. Redraws only touch the cells that changed.
+#[derive(Clone, Debug)]
+pub struct Terminal<'f, const LINES: usize, const COLS: usize> {
+    lines: [[u8; COLS]; LINES],
+    row: usize,
+    col: usize,
+    cursor: bool,
+    buffer: TextBuffer<'f, LINES, COLS>,
+}
+
+impl<'f, const LINES: usize, const COLS: usize> Terminal<'f, LINES, COLS> {
+    /// Empty terminal with its top-left corner at `position` drawn with `style`.
+    pub fn new(position: Point, style: TextStyle<'f>) -> Self {
+        Self {
+            lines: [[b' '; COLS]; LINES],
+            row: 0,
+            col: 0,
+            cursor: false,
+            buffer: TextBuffer::new(position, style),
+        }
+    }
+
+    /// Show a cursor at the write position.
+    pub fn with_cursor(mut self, cursor: bool) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Show or hide the cursor, e.g. toggled periodically to make it blink.
+    pub fn set_cursor(&mut self, cursor: bool) {
+        self.cursor = cursor;
+    }
+
+    pub fn cursor(&self) -> bool {
+        self.cursor
+    }
+
+    /// Line and column the next character is written to.
+    pub fn position(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn clear(&mut self) {
+        self.lines = [[b' '; COLS]; LINES];
+        self.row = 0;
+        self.col = 0;
+    }
+
+    fn new_line(&mut self) {
+        self.col = 0;
+        if self.row + 1 < LINES {
+            self.row += 1;
+        } else if LINES != 0 {
+            self.lines.copy_within(1.., 0);
+            self.lines[LINES - 1] = [b' '; COLS];
+        }
+    }
+
+    fn put(&mut self, c: char) {
+        match c {
+            '\n' => self.new_line(),
+            '\r' => self.col = 0,
+            _ if LINES == 0 || COLS == 0 => {}
+            _ => {
+                if self.col == COLS {
+                    self.new_line();
+                }
+                self.lines[self.row][self.col] = if c.is_ascii() { c as u8 } else { b'?' };
+                self.col += 1;
+            }
+        }
+    }
+
+    /// Area covered by the terminal.
+    pub fn bounding_box(&self) -> Rectangle {
+        self.buffer.bounding_box()
+    }
+
+    fn sync(&mut self) {
+        for (i, line) in self.lines.iter().enumerate() {
+            let mut line = *line;
+            if self.cursor && i == self.row {
+                if let Some(cell) = line.get_mut(self.col.min(COLS.saturating_sub(1))) {
+                    *cell = CURSOR;
+                }
+            }
+            self.buffer
+                .set_line(i, core::str::from_utf8(&line).unwrap_or(""));
+        }
+    }
+
+    /// Bring the display up to date, redrawing only the characters that changed.
+    pub fn draw_changed<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.sync();
+        self.buffer.draw_changed(target)
+    }
+
+    /// Redraw all characters, e.g. after the area was overwritten.
+    pub fn redraw<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.sync();
+        self.buffer.redraw(target)
+    }
+}
+
+impl<const LINES: usize, const COLS: usize> core::fmt::Write for Terminal<'_, LINES, COLS> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        s.chars().for_each(|c| self.put(c));
+        Ok(())
+    }
+}
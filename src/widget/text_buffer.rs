@@ -0,0 +1,136 @@
+//! Grid of text lines with per-character damage tracking.
+
+use core::fmt::Write;
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable,
+};
+
+use crate::text::{FmtBuf, HorizontalAlignment, Text, TextStyle, VerticalAlignment};
+
+/// `LINES` lines of `COLS` characters each, for readouts updated many times per second.
+///
+/// Lines are changed in memory with [`set_line`](Self::set_line) or
+/// [`write_line`](Self::write_line); [`draw_changed`](Self::draw_changed) then redraws only
+/// the character cells that differ from what is on the display, so refreshing a sensor
+/// value every frame costs next to nothing while it stays the same.
+#[derive(Clone, Debug)]
+pub struct TextBuffer<'f, const LINES: usize, const COLS: usize> {
+    position: Point,
+    style: TextStyle<'f>,
+    lines: [[u8; COLS]; LINES],
+    shown: Option<[[u8; COLS]; LINES]>,
+}
+
+impl<'f, const LINES: usize, const COLS: usize> TextBuffer<'f, LINES, COLS> {
+    /// Blank buffer with its top-left corner at `position` drawn with `style`.
+    pub fn new(position: Point, style: TextStyle<'f>) -> Self {
+        Self {
+            position,
+            style: style.with_alignment(HorizontalAlignment::Left, VerticalAlignment::Top),
+            lines: [[b' '; COLS]; LINES],
+            shown: None,
+        }
+    }
+
+    /// Vertical distance between the tops of two neighbouring lines.
+    fn line_height(&self) -> i32 {
+        self.style.font.height as i32 + 1
+    }
+
+    /// Replace `line` with `text`, truncated or padded with blanks to `COLS` characters.
+    /// Characters outside ASCII are shown as `?`.
+    pub fn set_line(&mut self, line: usize, text: &str) {
+        let Some(cells) = self.lines.get_mut(line) else {
+            return;
+        };
+        let mut chars = text.chars();
+        for cell in cells {
+            *cell = match chars.next() {
+                Some(c) if c.is_ascii() => c as u8,
+                Some(_) => b'?',
+                None => b' ',
+            };
+        }
+    }
+
+    /// Replace `line` with formatted text, e.g. `buffer.write_line(0, format_args!("{t}C"))`.
+    pub fn write_line(&mut self, line: usize, args: core::fmt::Arguments<'_>) {
+        let mut buf = FmtBuf::<COLS>::new();
+        let _ = buf.write_fmt(args);
+        self.set_line(line, buf.as_str());
+    }
+
+    /// Text of `line` including padding, empty if out of range.
+    pub fn line(&self, line: usize) -> &str {
+        self.lines
+            .get(line)
+            .and_then(|cells| core::str::from_utf8(cells).ok())
+            .unwrap_or("")
+    }
+
+    pub fn clear(&mut self) {
+        self.lines = [[b' '; COLS]; LINES];
+    }
+
+    /// Whether any character differs from the display.
+    pub fn is_dirty(&self) -> bool {
+        self.shown != Some(self.lines)
+    }
+
+    /// Area covered by the buffer.
+    pub fn bounding_box(&self) -> Rectangle {
+        let width =
+            (self.style.advance() * COLS as u32).saturating_sub(self.style.font.spacing as u32);
+        let height = (self.line_height() * LINES as i32 - 1).max(0) as u32;
+        Rectangle::new(self.position, Size::new(width, height))
+    }
+
+    fn draw_cells<D>(&mut self, target: &mut D, all: bool) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        if !all && !self.is_dirty() {
+            return Ok(());
+        }
+        let advance = self.style.advance() as i32;
+        let cell_size = Size::new(self.style.glyph_width(), self.style.font.height as u32);
+        for (row, cells) in self.lines.iter().enumerate() {
+            for (col, c) in cells.iter().enumerate() {
+                if !all && self.shown.is_some_and(|shown| shown[row][col] == *c) {
+                    continue;
+                }
+                let cell = Rectangle::new(
+                    self.position
+                        + Point::new(col as i32 * advance, row as i32 * self.line_height()),
+                    cell_size,
+                );
+                target.fill_solid(&cell, self.style.color.invert())?;
+                let text = core::str::from_utf8(core::slice::from_ref(c)).unwrap_or("");
+                Text::new(text, cell.top_left, self.style).draw(target)?;
+            }
+        }
+        self.shown = Some(self.lines);
+        Ok(())
+    }
+
+    /// Bring the display up to date, redrawing only the characters that changed.
+    pub fn draw_changed<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.draw_cells(target, false)
+    }
+
+    /// Redraw all characters, e.g. after the area was overwritten.
+    pub fn redraw<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.draw_cells(target, true)
+    }
+}
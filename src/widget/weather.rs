@@ -0,0 +1,153 @@
+//! Weather summary with a pictogram, temperature and condition.
+
+use core::fmt::Write;
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Drawable,
+};
+
+use crate::{
+    font::{CONDENSED_4X7, TINY_3X5},
+    icon::Icon,
+    text::{FmtBuf, HorizontalAlignment, Text, TextStyle, VerticalAlignment},
+};
+
+/// Weather condition shown by [`Weather`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Condition {
+    #[default]
+    Clear,
+    Cloudy,
+    Rain,
+    Snow,
+    Storm,
+}
+
+impl Condition {
+    pub fn icon(self) -> Icon {
+        match self {
+            Self::Clear => Icon::Sun,
+            Self::Cloudy => Icon::Cloud,
+            Self::Rain => Icon::Rain,
+            Self::Snow => Icon::Snow,
+            Self::Storm => Icon::Storm,
+        }
+    }
+
+    /// Short label, at most 6 characters.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Clear => "CLEAR",
+            Self::Cloudy => "CLOUDY",
+            Self::Rain => "RAIN",
+            Self::Snow => "SNOW",
+            Self::Storm => "STORM",
+        }
+    }
+}
+
+/// Arrangement of a [`Weather`] widget for the panels it covers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeatherLayout {
+    /// Single 32x16 panel: pictogram and temperature on top, small condition label below.
+    #[default]
+    Single,
+    /// Two panels side by side, 64x16: pictogram on the left, temperature and condition
+    /// stacked next to it.
+    Double,
+}
+
+impl WeatherLayout {
+    pub fn size(self) -> Size {
+        match self {
+            Self::Single => Size::new(32, 16),
+            Self::Double => Size::new(64, 16),
+        }
+    }
+}
+
+/// Pictogram, temperature in degrees Celsius and condition label.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Weather {
+    position: Point,
+    layout: WeatherLayout,
+    temperature: i16,
+    condition: Condition,
+}
+
+impl Weather {
+    /// Widget with its top-left corner at `position`, showing 0 C and clear sky.
+    pub fn new(position: Point, layout: WeatherLayout) -> Self {
+        Self {
+            position,
+            layout,
+            temperature: 0,
+            condition: Condition::Clear,
+        }
+    }
+
+    pub fn set(&mut self, temperature: i16, condition: Condition) {
+        self.temperature = temperature;
+        self.condition = condition;
+    }
+
+    pub fn temperature(&self) -> i16 {
+        self.temperature
+    }
+
+    pub fn condition(&self) -> Condition {
+        self.condition
+    }
+
+    /// Area covered by the widget.
+    pub fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(self.position, self.layout.size())
+    }
+
+    fn area(&self, x: i32, y: i32, width: u32, height: u32) -> Rectangle {
+        Rectangle::new(self.position + Point::new(x, y), Size::new(width, height))
+    }
+}
+
+impl Drawable for Weather {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        target.fill_solid(&self.bounding_box(), BinaryColor::Off)?;
+        let mut temperature = FmtBuf::<8>::new();
+        let _ = write!(temperature, "{}C", self.temperature);
+        let large = TextStyle::new(&CONDENSED_4X7);
+        let (icon, temp_area, temp_style, label_area, label_style) = match self.layout {
+            WeatherLayout::Single => (
+                Point::zero(),
+                self.area(9, 0, 23, 8),
+                large.with_alignment(HorizontalAlignment::Right, VerticalAlignment::Top),
+                self.area(0, 10, 32, 6),
+                TextStyle::new(&TINY_3X5)
+                    .with_alignment(HorizontalAlignment::Center, VerticalAlignment::Top),
+            ),
+            WeatherLayout::Double => (
+                Point::new(0, 4),
+                self.area(10, 0, 54, 8),
+                large,
+                self.area(10, 8, 54, 8),
+                large,
+            ),
+        };
+        self.condition
+            .icon()
+            .at(self.position + icon)
+            .draw(target)?;
+        Text::in_area(temperature.as_str(), &temp_area, temp_style).draw(target)?;
+        Text::in_area(self.condition.label(), &label_area, label_style).draw(target)?;
+        Ok(())
+    }
+}